@@ -0,0 +1,111 @@
+-- Baseline schema, as created by the former docker init script. Every
+-- statement only creates what is missing, so that databases set up by that
+-- script can adopt migrations; later changes each have their own migration.
+
+-- Create schema
+CREATE SCHEMA IF NOT EXISTS pbtar;
+
+-- Set search path for this migration's transaction
+SET LOCAL search_path TO pbtar, public;
+
+-- Create items table (customize based on what your app needs)
+CREATE TABLE IF NOT EXISTS items (
+    id SERIAL PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    status VARCHAR(50) DEFAULT 'active',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create publishers table
+CREATE TABLE IF NOT EXISTS publishers (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create scenarios table
+CREATE TABLE IF NOT EXISTS scenarios (
+    id SERIAL PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    type VARCHAR(50) NOT NULL, -- 'normative', 'exploratory', etc.
+    temperature_target VARCHAR(50), -- e.g., '1.5°C', '2°C', etc.
+    description TEXT,
+    publisher_id INTEGER REFERENCES publishers(id),
+    published_date DATE,
+    target_year INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create regions table
+CREATE TABLE IF NOT EXISTS regions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    parent_id INTEGER REFERENCES regions(id) NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create stakeholders table
+CREATE TABLE IF NOT EXISTS stakeholders (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    type VARCHAR(50) NOT NULL, -- 'Government Agency', 'NGO', 'Corporation', etc.
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create sectors table
+CREATE TABLE IF NOT EXISTS sectors (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create junction tables for many-to-many relationships
+
+-- Scenarios to Regions
+CREATE TABLE IF NOT EXISTS scenario_regions (
+    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
+    region_id INTEGER REFERENCES regions(id) ON DELETE CASCADE,
+    PRIMARY KEY (scenario_id, region_id)
+);
+
+-- Scenarios to Stakeholders
+CREATE TABLE IF NOT EXISTS scenario_stakeholders (
+    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
+    stakeholder_id INTEGER REFERENCES stakeholders(id) ON DELETE CASCADE,
+    PRIMARY KEY (scenario_id, stakeholder_id)
+);
+
+-- Scenarios to Sectors
+CREATE TABLE IF NOT EXISTS scenario_sectors (
+    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
+    sector_id INTEGER REFERENCES sectors(id) ON DELETE CASCADE,
+    PRIMARY KEY (scenario_id, sector_id)
+);
+
+-- Create index for performance
+CREATE INDEX IF NOT EXISTS idx_scenarios_publisher_id ON scenarios(publisher_id);
+CREATE INDEX IF NOT EXISTS idx_regions_parent_id ON regions(parent_id);
+
+-- Create function to update timestamps
+CREATE OR REPLACE FUNCTION update_modified_column()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE 'plpgsql';
+
+-- Create triggers for timestamp updates
+CREATE OR REPLACE TRIGGER update_items_modtime
+    BEFORE UPDATE ON items
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_column();
+
+CREATE OR REPLACE TRIGGER update_scenarios_modtime
+    BEFORE UPDATE ON scenarios
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_column();
@@ -0,0 +1,44 @@
+-- Full-text search over scenario titles, publisher names and descriptions
+SET LOCAL search_path TO pbtar, public;
+
+ALTER TABLE scenarios ADD COLUMN IF NOT EXISTS search_vector TSVECTOR; -- maintained by update_scenario_search_vector()
+
+CREATE INDEX IF NOT EXISTS idx_scenarios_search_vector ON scenarios USING GIN (search_vector);
+
+-- Keep the full-text search vector in sync with the title, publisher name
+-- and description (weighted in that order)
+CREATE OR REPLACE FUNCTION update_scenario_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector =
+        setweight(to_tsvector('english', COALESCE(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', COALESCE(
+            (SELECT name FROM pbtar.publishers WHERE id = NEW.publisher_id), '')), 'B') ||
+        setweight(to_tsvector('english', COALESCE(NEW.description, '')), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE TRIGGER update_scenarios_search_vector
+    BEFORE INSERT OR UPDATE OF title, description, publisher_id ON scenarios
+    FOR EACH ROW
+    EXECUTE FUNCTION update_scenario_search_vector();
+
+-- Re-index a publisher's scenarios when it is renamed
+CREATE OR REPLACE FUNCTION refresh_publisher_scenarios_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE pbtar.scenarios SET title = title WHERE publisher_id = NEW.id;
+    RETURN NEW;
+END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE TRIGGER refresh_publishers_search_vector
+    AFTER UPDATE OF name ON publishers
+    FOR EACH ROW
+    EXECUTE FUNCTION refresh_publisher_scenarios_search_vector();
+
+-- Index existing scenarios without touching their modification time
+ALTER TABLE scenarios DISABLE TRIGGER update_scenarios_modtime;
+UPDATE scenarios SET title = title WHERE search_vector IS NULL;
+ALTER TABLE scenarios ENABLE TRIGGER update_scenarios_modtime;
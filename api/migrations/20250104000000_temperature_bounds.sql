@@ -0,0 +1,11 @@
+-- Temperature targets broken into bounds for range filtering. Existing rows
+-- are filled in by the API after migrating, as the labels are parsed there.
+SET LOCAL search_path TO pbtar, public;
+
+ALTER TABLE scenarios
+    ADD COLUMN IF NOT EXISTS temperature_lower DOUBLE PRECISION, -- bounds in °C parsed from temperature_target
+    ADD COLUMN IF NOT EXISTS temperature_upper DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS temperature_probability DOUBLE PRECISION, -- e.g., 0.5 for '1.5°C (50%)'
+    ADD COLUMN IF NOT EXISTS temperature_overshoot BOOLEAN;
+
+CREATE INDEX IF NOT EXISTS idx_scenarios_temperature ON scenarios(temperature_upper, temperature_lower);
@@ -0,0 +1,41 @@
+-- Yearly pathway values per variable, region and optional sector
+SET LOCAL search_path TO pbtar, public;
+
+-- Create units table
+CREATE TABLE IF NOT EXISTS units (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) UNIQUE NOT NULL, -- e.g., 'Mt CO2/yr', 'GW', 'EJ/yr'
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create variables table
+CREATE TABLE IF NOT EXISTS variables (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) UNIQUE NOT NULL, -- IAMC style, e.g., 'Emissions|CO2'
+    unit_id INTEGER NOT NULL REFERENCES units(id),
+    description TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Yearly pathway values, keyed by scenario, variable, region and optional sector
+CREATE TABLE IF NOT EXISTS timeseries (
+    id SERIAL PRIMARY KEY,
+    scenario_id INTEGER NOT NULL REFERENCES scenarios(id) ON DELETE CASCADE,
+    variable_id INTEGER NOT NULL REFERENCES variables(id),
+    region_id INTEGER NOT NULL REFERENCES regions(id),
+    sector_id INTEGER REFERENCES sectors(id),
+    year INTEGER NOT NULL,
+    value DOUBLE PRECISION NOT NULL
+);
+
+-- One value per series and year. Sector-less series get their own index, as
+-- NULL sectors would otherwise never conflict (NULLS NOT DISTINCT needs
+-- PostgreSQL 15)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_timeseries_series_year
+    ON timeseries(scenario_id, variable_id, region_id, sector_id, year)
+    WHERE sector_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_timeseries_series_year_no_sector
+    ON timeseries(scenario_id, variable_id, region_id, year)
+    WHERE sector_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_timeseries_variable_id ON timeseries(variable_id);
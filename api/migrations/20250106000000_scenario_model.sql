@@ -0,0 +1,2 @@
+-- Integrated assessment model behind each pathway
+ALTER TABLE pbtar.scenarios ADD COLUMN IF NOT EXISTS model VARCHAR(255); -- e.g., 'REMIND-MAgPIE 3.2'
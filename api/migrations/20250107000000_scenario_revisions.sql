@@ -0,0 +1,47 @@
+-- Revision history of scenarios
+SET LOCAL search_path TO pbtar, public;
+
+-- Full snapshots of every scenario revision, kept after the scenario is deleted
+CREATE TABLE IF NOT EXISTS scenario_revisions (
+    id SERIAL PRIMARY KEY,
+    scenario_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+    snapshot JSONB NOT NULL, -- built by scenario_snapshot()
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    username VARCHAR(100), -- kept when the user is deleted
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (scenario_id, revision)
+);
+
+-- Snapshot of a scenario and its associations, as stored in scenario_revisions
+CREATE OR REPLACE FUNCTION scenario_snapshot(scenario INTEGER)
+RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'title', s.title,
+        'type_name', s.type,
+        'model', s.model,
+        'temperature_target', s.temperature_target,
+        'description', s.description,
+        'publisher_id', s.publisher_id,
+        'published_date', s.published_date,
+        'target_year', s.target_year,
+        'region_ids', COALESCE((
+            SELECT jsonb_agg(region_id ORDER BY region_id)
+            FROM pbtar.scenario_regions WHERE scenario_id = s.id), '[]'),
+        'stakeholder_ids', COALESCE((
+            SELECT jsonb_agg(stakeholder_id ORDER BY stakeholder_id)
+            FROM pbtar.scenario_stakeholders WHERE scenario_id = s.id), '[]'),
+        'sector_ids', COALESCE((
+            SELECT jsonb_agg(sector_id ORDER BY sector_id)
+            FROM pbtar.scenario_sectors WHERE scenario_id = s.id), '[]')
+    )
+    FROM pbtar.scenarios s
+    WHERE s.id = scenario;
+$$ LANGUAGE sql STABLE;
+
+-- Record existing scenarios as their first revision
+INSERT INTO scenario_revisions (scenario_id, revision, action, snapshot, created_at)
+SELECT s.id, 1, 'create', scenario_snapshot(s.id), COALESCE(s.created_at, NOW())
+FROM scenarios s
+WHERE NOT EXISTS (SELECT 1 FROM scenario_revisions r WHERE r.scenario_id = s.id);
@@ -0,0 +1,46 @@
+-- Lineage between scenarios
+SET LOCAL search_path TO pbtar, public;
+
+-- 'supersedes' for a newer edition, 'derived_from' for a variant built on
+-- another scenario, 'part_of_series' for a scenario belonging to the same
+-- series as another
+CREATE TABLE IF NOT EXISTS scenario_relations (
+    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
+    related_scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
+    relation VARCHAR(20) NOT NULL CHECK (relation IN ('supersedes', 'derived_from', 'part_of_series')),
+    PRIMARY KEY (scenario_id, related_scenario_id, relation),
+    CHECK (scenario_id <> related_scenario_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_scenario_relations_related ON scenario_relations(related_scenario_id, relation);
+
+-- Snapshots now include relations
+CREATE OR REPLACE FUNCTION scenario_snapshot(scenario INTEGER)
+RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'title', s.title,
+        'type_name', s.type,
+        'model', s.model,
+        'temperature_target', s.temperature_target,
+        'description', s.description,
+        'publisher_id', s.publisher_id,
+        'published_date', s.published_date,
+        'target_year', s.target_year,
+        'region_ids', COALESCE((
+            SELECT jsonb_agg(region_id ORDER BY region_id)
+            FROM pbtar.scenario_regions WHERE scenario_id = s.id), '[]'),
+        'stakeholder_ids', COALESCE((
+            SELECT jsonb_agg(stakeholder_id ORDER BY stakeholder_id)
+            FROM pbtar.scenario_stakeholders WHERE scenario_id = s.id), '[]'),
+        'sector_ids', COALESCE((
+            SELECT jsonb_agg(sector_id ORDER BY sector_id)
+            FROM pbtar.scenario_sectors WHERE scenario_id = s.id), '[]'),
+        'relations', COALESCE((
+            SELECT jsonb_agg(
+                jsonb_build_object('relation', relation, 'scenario_id', related_scenario_id)
+                ORDER BY relation, related_scenario_id)
+            FROM pbtar.scenario_relations WHERE scenario_id = s.id), '[]')
+    )
+    FROM pbtar.scenarios s
+    WHERE s.id = scenario;
+$$ LANGUAGE sql STABLE;
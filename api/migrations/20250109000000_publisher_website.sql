@@ -0,0 +1,2 @@
+-- Publisher homepages
+ALTER TABLE pbtar.publishers ADD COLUMN IF NOT EXISTS website VARCHAR(255);
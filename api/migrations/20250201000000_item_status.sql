@@ -0,0 +1,13 @@
+-- Items track reading-list entries and tasks through a fixed set of states
+UPDATE pbtar.items SET status = 'active' WHERE status IS NULL;
+UPDATE pbtar.items SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE pbtar.items SET updated_at = created_at WHERE updated_at IS NULL;
+
+ALTER TABLE pbtar.items
+    ALTER COLUMN status SET NOT NULL,
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL,
+    ADD CONSTRAINT items_status_check
+        CHECK (status IN ('active', 'in_progress', 'done', 'archived'));
+
+CREATE INDEX IF NOT EXISTS idx_items_status ON pbtar.items(status);
@@ -1,110 +1,8 @@
--- Create schema
-CREATE SCHEMA IF NOT EXISTS pbtar;
-
--- Set search path
-SET search_path TO pbtar, public;
-
--- Create items table (customize based on what your app needs)
-CREATE TABLE IF NOT EXISTS items (
-    id SERIAL PRIMARY KEY,
-    title VARCHAR(255) NOT NULL,
-    description TEXT,
-    status VARCHAR(50) DEFAULT 'active',
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
-
--- Create publishers table
-CREATE TABLE IF NOT EXISTS publishers (
-    id SERIAL PRIMARY KEY,
-    name VARCHAR(100) UNIQUE NOT NULL,
-    description TEXT,
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
-
--- Create scenarios table
-CREATE TABLE IF NOT EXISTS scenarios (
-    id SERIAL PRIMARY KEY,
-    title VARCHAR(255) NOT NULL,
-    type VARCHAR(50) NOT NULL, -- 'normative', 'exploratory', etc.
-    temperature_target VARCHAR(50), -- e.g., '1.5°C', '2°C', etc.
-    description TEXT,
-    publisher_id INTEGER REFERENCES publishers(id),
-    published_date DATE,
-    target_year INTEGER,
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
-
--- Create regions table
-CREATE TABLE IF NOT EXISTS regions (
-    id SERIAL PRIMARY KEY,
-    name VARCHAR(100) UNIQUE NOT NULL,
-    parent_id INTEGER REFERENCES regions(id) NULL,
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
-
--- Create stakeholders table
-CREATE TABLE IF NOT EXISTS stakeholders (
-    id SERIAL PRIMARY KEY,
-    name VARCHAR(100) UNIQUE NOT NULL,
-    type VARCHAR(50) NOT NULL, -- 'Government Agency', 'NGO', 'Corporation', etc.
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
+-- Sample data for development and demos, loaded by `api seed` or at startup
+-- with SEED_SAMPLE_DATA=true. Not part of the schema migrations.
 
--- Create sectors table
-CREATE TABLE IF NOT EXISTS sectors (
-    id SERIAL PRIMARY KEY,
-    name VARCHAR(100) UNIQUE NOT NULL,
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
-
--- Create junction tables for many-to-many relationships
+SET LOCAL search_path TO pbtar, public;
 
--- Scenarios to Regions
-CREATE TABLE IF NOT EXISTS scenario_regions (
-    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
-    region_id INTEGER REFERENCES regions(id) ON DELETE CASCADE,
-    PRIMARY KEY (scenario_id, region_id)
-);
-
--- Scenarios to Stakeholders
-CREATE TABLE IF NOT EXISTS scenario_stakeholders (
-    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
-    stakeholder_id INTEGER REFERENCES stakeholders(id) ON DELETE CASCADE,
-    PRIMARY KEY (scenario_id, stakeholder_id)
-);
-
--- Scenarios to Sectors
-CREATE TABLE IF NOT EXISTS scenario_sectors (
-    scenario_id INTEGER REFERENCES scenarios(id) ON DELETE CASCADE,
-    sector_id INTEGER REFERENCES sectors(id) ON DELETE CASCADE,
-    PRIMARY KEY (scenario_id, sector_id)
-);
-
--- Create index for performance
-CREATE INDEX IF NOT EXISTS idx_scenarios_publisher_id ON scenarios(publisher_id);
-CREATE INDEX IF NOT EXISTS idx_regions_parent_id ON regions(parent_id);
-
--- Create function to update timestamps
-CREATE OR REPLACE FUNCTION update_modified_column()
-RETURNS TRIGGER AS $$
-BEGIN
-    NEW.updated_at = NOW();
-    RETURN NEW;
-END;
-$$ LANGUAGE 'plpgsql';
-
--- Create triggers for timestamp updates
-CREATE TRIGGER update_items_modtime
-    BEFORE UPDATE ON items
-    FOR EACH ROW
-    EXECUTE FUNCTION update_modified_column();
-
-CREATE TRIGGER update_scenarios_modtime
-    BEFORE UPDATE ON scenarios
-    FOR EACH ROW
-    EXECUTE FUNCTION update_modified_column();
 
 -- Insert sample data for publishers
 INSERT INTO publishers (name) VALUES 
@@ -156,12 +54,14 @@ INSERT INTO sectors (name) VALUES
 ON CONFLICT (name) DO NOTHING;
 
 -- Insert sample scenarios
-INSERT INTO scenarios (title, type, temperature_target, description, publisher_id, published_date, target_year)
+INSERT INTO scenarios (title, type, temperature_target, temperature_lower, temperature_upper, description, publisher_id, published_date, target_year)
 VALUES 
 (
     'Net Zero by 2050', 
     'normative', 
     '1.5°C', 
+    1.5,
+    1.5,
     'A comprehensive pathway for the global energy sector to achieve net zero emissions by 2050',
     (SELECT id FROM publishers WHERE name = 'IEA'),
     '2023-06-15',
@@ -171,6 +71,8 @@ VALUES
     'Southeast Asia Energy Outlook 2024', 
     'exploratory', 
     NULL, 
+    NULL,
+    NULL,
     'Analysis of energy transition pathways in Southeast Asia',
     (SELECT id FROM publishers WHERE name = 'IEA'),
     '2024-01-20',
@@ -180,6 +82,8 @@ VALUES
     'World Energy Transitions Outlook', 
     'normative', 
     '1.5-2°C', 
+    1.5,
+    2,
     'A pathway to achieve the 1.5°C Paris Agreement goal with focus on renewable energy',
     (SELECT id FROM publishers WHERE name = 'IRENA'),
     '2023-09-10',
@@ -232,4 +136,49 @@ WHERE s.title = 'Southeast Asia Energy Outlook 2024' AND sec.name IN ('Power', '
 INSERT INTO scenario_sectors (scenario_id, sector_id)
 SELECT s.id, sec.id 
 FROM scenarios s, sectors sec
-WHERE s.title = 'World Energy Transitions Outlook' AND sec.name IN ('Power', 'Buildings', 'Manufacturing');
\ No newline at end of file
+WHERE s.title = 'World Energy Transitions Outlook' AND sec.name IN ('Power', 'Buildings', 'Manufacturing');
+
+-- Record the sample scenarios as their first revision
+INSERT INTO scenario_revisions (scenario_id, revision, action, snapshot)
+SELECT id, 1, 'create', scenario_snapshot(id) FROM scenarios;
+
+-- Insert sample units and variables
+INSERT INTO units (name) VALUES
+('Mt CO2/yr'),
+('GW')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO variables (name, unit_id, description) VALUES
+('Emissions|CO2', (SELECT id FROM units WHERE name = 'Mt CO2/yr'), 'Total CO2 emissions'),
+('Capacity|Electricity|Solar', (SELECT id FROM units WHERE name = 'GW'), 'Installed solar PV capacity')
+ON CONFLICT (name) DO NOTHING;
+
+-- Insert sample (illustrative) pathway values
+INSERT INTO timeseries (scenario_id, variable_id, region_id, sector_id, year, value)
+SELECT s.id, v.id, r.id, NULL, data.year, data.value
+FROM (VALUES
+    ('Emissions|CO2', 2020, 33900.0),
+    ('Emissions|CO2', 2030, 21100.0),
+    ('Emissions|CO2', 2040, 6600.0),
+    ('Emissions|CO2', 2050, 0.0),
+    ('Capacity|Electricity|Solar', 2020, 740.0),
+    ('Capacity|Electricity|Solar', 2030, 5000.0),
+    ('Capacity|Electricity|Solar', 2040, 11000.0),
+    ('Capacity|Electricity|Solar', 2050, 14500.0)
+) AS data (variable, year, value)
+JOIN variables v ON v.name = data.variable
+JOIN scenarios s ON s.title = 'Net Zero by 2050'
+JOIN regions r ON r.name = 'Global';
+
+INSERT INTO timeseries (scenario_id, variable_id, region_id, sector_id, year, value)
+SELECT s.id, v.id, r.id, sec.id, data.year, data.value
+FROM (VALUES
+    (2020, 720.0),
+    (2030, 810.0),
+    (2040, 760.0),
+    (2050, 640.0)
+) AS data (year, value)
+JOIN variables v ON v.name = 'Emissions|CO2'
+JOIN scenarios s ON s.title = 'Southeast Asia Energy Outlook 2024'
+JOIN regions r ON r.name = 'Southeast Asia'
+JOIN sectors sec ON sec.name = 'Power';
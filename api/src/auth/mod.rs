@@ -0,0 +1,117 @@
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::models::{Role, User};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: i32,
+    pub username: String,
+    pub role: Role,
+    pub exp: i64,
+}
+
+/// Signs a token for `user` that expires after `config.jwt_expiration` seconds.
+pub fn issue_token(config: &Config, user: &User) -> Result<String, ApiError> {
+    let expiration = Duration::try_seconds(config.jwt_expiration)
+        .and_then(|lifetime| Utc::now().checked_add_signed(lifetime))
+        .ok_or_else(|| ApiError::InternalError("Token expiration is out of range".into()))?
+        .timestamp();
+
+    let claims = Claims {
+        sub: user.id,
+        username: user.username.clone(),
+        role: user.role,
+        exp: expiration,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+    .map_err(|_| ApiError::InternalError("Failed to generate token".into()))
+}
+
+pub fn verify_token(config: &Config, token: &str) -> Result<Claims, ApiError> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .map_err(|_| ApiError::AuthError("Invalid or expired token".into()))
+}
+
+/// Extractor for routes that require a logged-in user.
+///
+/// Reads the `Authorization: Bearer <token>` header and rejects the request
+/// with `ApiError::AuthError` when it is missing or does not verify, or when
+/// its user has since been deleted. The role is read from the database
+/// rather than the token, so that role changes apply immediately.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(pub Claims);
+
+impl AuthenticatedUser {
+    /// Rejects the request with `ApiError::ForbiddenError` unless the user
+    /// holds `role` or a higher one.
+    pub fn require(&self, role: Role) -> Result<(), ApiError> {
+        if self.0.role >= role {
+            Ok(())
+        } else {
+            Err(ApiError::ForbiddenError(format!(
+                "This action requires the {} role",
+                role
+            )))
+        }
+    }
+
+    fn verify_request(req: &HttpRequest) -> Result<Claims, ApiError> {
+        let config = req
+            .app_data::<web::Data<Config>>()
+            .ok_or_else(|| ApiError::InternalError("Configuration is not registered".into()))?;
+
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::AuthError("Missing bearer token".into()))?;
+
+        verify_token(config, token.trim())
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let claims = Self::verify_request(req);
+        let db = req.app_data::<web::Data<PgPool>>().cloned();
+
+        Box::pin(async move {
+            let mut claims = claims?;
+            let db = db.ok_or_else(|| ApiError::InternalError("Database pool is not registered".into()))?;
+
+            let user = sqlx::query!(
+                r#"SELECT username, role as "role: Role" FROM pbtar.users WHERE id = $1"#,
+                claims.sub
+            )
+            .fetch_optional(db.get_ref())
+            .await
+            .map_err(ApiError::DbError)?
+            .ok_or_else(|| ApiError::AuthError("This account no longer exists".into()))?;
+
+            claims.username = user.username;
+            claims.role = user.role;
+            Ok(AuthenticatedUser(claims))
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,105 @@
+use sqlx::PgPool;
+
+use crate::{db, import};
+
+const USAGE: &str = "Usage:
+    api migrate
+    api seed
+    api import-scenarios <file.csv> [--dry-run]
+    api import-iamc <file.csv|file.xlsx> [--dry-run] [--create-regions]";
+
+/// Runs a one-off admin command instead of the HTTP server. Prints the
+/// outcome to stdout and returns the process exit code.
+pub async fn run(args: &[String], db: &PgPool) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("migrate") => migrate(db).await,
+        Some("seed") => seed(db).await,
+        Some("import-scenarios") => import_scenarios(&args[1..], db).await,
+        Some("import-iamc") => import_iamc(&args[1..], db).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+async fn migrate(db: &PgPool) -> i32 {
+    if let Err(e) = db::migrate(db).await {
+        eprintln!("Migration failed: {}", e);
+        return 1;
+    }
+
+    let version = db::MIGRATOR.iter().map(|m| m.version).max().unwrap_or_default();
+    println!("Database schema is up to date at version {}", version);
+    0
+}
+
+async fn seed(db: &PgPool) -> i32 {
+    match db::seed(db).await {
+        Ok(true) => {
+            println!("Loaded the sample data");
+            0
+        }
+        Ok(false) => {
+            println!("The database already holds scenarios; sample data not loaded");
+            0
+        }
+        Err(e) => {
+            eprintln!("Seeding failed: {}", e);
+            1
+        }
+    }
+}
+
+async fn import_scenarios(args: &[String], db: &PgPool) -> i32 {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let Some(data) = read_input(args) else {
+        return 2;
+    };
+
+    match import::import_scenarios_csv(db, &data, dry_run, None).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+            if report.rejected > 0 { 1 } else { 0 }
+        }
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            1
+        }
+    }
+}
+
+async fn import_iamc(args: &[String], db: &PgPool) -> i32 {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let create_regions = args.iter().any(|arg| arg == "--create-regions");
+    let Some(data) = read_input(args) else {
+        return 2;
+    };
+
+    match import::import_iamc(db, &data, dry_run, create_regions).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+            if report.rows_rejected > 0 { 1 } else { 0 }
+        }
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            1
+        }
+    }
+}
+
+/// Reads the file named by the first non-flag argument.
+fn read_input(args: &[String]) -> Option<Vec<u8>> {
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        eprintln!("{}", USAGE);
+        return None;
+    };
+
+    match std::fs::read(path) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            None
+        }
+    }
+}
@@ -1,18 +1,221 @@
-use serde::Deserialize;
 use std::env;
+use std::time::Duration;
+
+use ::config::{Environment, File};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::cors::{self, CorsMode};
+
+/// Longest token lifetime accepted for `JWT_EXPIRATION`: one year.
+const MAX_JWT_EXPIRATION: i64 = 365 * 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to load configuration: {0}")]
+    Load(#[from] ::config::ConfigError),
 
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+}
+
+/// How log records are written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines, as printed by `env_logger`.
+    #[default]
+    Text,
+    /// One JSON object per record, for log collectors.
+    Json,
+}
+
+/// Settings of the API, read by `Config::load` from built-in defaults, then
+/// an optional configuration file, then environment variables named after
+/// the fields in upper case (`DATABASE_URL`, `DB_MAX_CONNECTIONS`, ...).
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
+    #[serde(default)]
     pub database_url: String,
+    /// Address the HTTP server listens on, as `host:port`.
+    pub bind_address: String,
+    pub db_max_connections: u32,
+    pub db_min_connections: u32,
+    /// How long a request may wait for a pooled connection, in seconds.
+    pub db_acquire_timeout: u64,
+    /// How long a connection may sit idle in the pool, in seconds.
+    pub db_idle_timeout: u64,
+    /// Attempts to reach the database at startup, `db_connect_retry_delay`
+    /// seconds apart.
+    pub db_connect_retries: u32,
+    pub db_connect_retry_delay: u64,
+    /// How long a client may take to send the request head, in seconds.
+    pub request_timeout: u64,
+    /// How long in-flight requests may take to finish on shutdown, in
+    /// seconds.
+    pub shutdown_timeout: u64,
+    /// How long the readiness probe waits for the database, in seconds.
+    pub health_check_timeout: u64,
+    /// `strict` to allow only the listed origins, methods and headers, or
+    /// `permissive` to allow anything, for local development.
+    pub cors_mode: CorsMode,
+    /// Origins allowed to call the API from a browser, each exact, `*`, or
+    /// with a wildcard subdomain (`https://*.example.org`). Comma-separated
+    /// in `CORS_ALLOWED_ORIGINS`, like the methods and headers.
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    /// Let browsers send cookies and credentials along.
+    pub cors_allow_credentials: bool,
+    /// How long browsers may cache a preflight response, in seconds.
+    pub cors_max_age: usize,
+    #[serde(default)]
+    pub jwt_secret: String,
+    /// Lifetime of issued tokens, in seconds.
+    pub jwt_expiration: i64,
+    pub log_format: LogFormat,
+    /// Apply pending schema migrations before serving.
+    pub run_migrations: bool,
+    /// Load the sample data at startup if the database has no scenarios.
+    pub seed_sample_data: bool,
 }
 
 impl Config {
-    pub fn from_env() -> Self {
-        let database_url = env::var("DATABASE_URL")
-            .expect("DATABASE_URL must be set");
-            
-        Self {
-            database_url,
+    /// Builds the configuration and validates it. The file is the one named
+    /// by `CONFIG_FILE`, or else `config.toml` (or `.json`, `.yaml`) in the
+    /// working directory if present.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => File::with_name(&path).required(true),
+            Err(_) => File::with_name("config").required(false),
+        };
+
+        let mut config: Self = ::config::Config::builder()
+            .set_default("bind_address", "0.0.0.0:8080")?
+            .set_default("db_max_connections", 10)?
+            .set_default("db_min_connections", 0)?
+            .set_default("db_acquire_timeout", 3)?
+            .set_default("db_idle_timeout", 600)?
+            .set_default("db_connect_retries", 10)?
+            .set_default("db_connect_retry_delay", 5)?
+            .set_default("request_timeout", 5)?
+            .set_default("shutdown_timeout", 30)?
+            .set_default("health_check_timeout", 2)?
+            .set_default("cors_mode", "strict")?
+            .set_default("cors_allowed_origins", Vec::<String>::new())?
+            .set_default("cors_allowed_methods", vec!["GET", "POST", "PUT", "PATCH", "DELETE"])?
+            .set_default("cors_allowed_headers", vec!["Authorization", "Accept", "Content-Type"])?
+            .set_default("cors_allow_credentials", false)?
+            .set_default("cors_max_age", 3600)?
+            .set_default("jwt_expiration", 86400)?
+            .set_default("log_format", "text")?
+            .set_default("run_migrations", true)?
+            .set_default("seed_sample_data", false)?
+            .add_source(file)
+            .add_source(
+                Environment::default()
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("cors_allowed_origins")
+                    .with_list_parse_key("cors_allowed_methods")
+                    .with_list_parse_key("cors_allowed_headers"),
+            )
+            .build()?
+            .try_deserialize()?;
+
+        // An empty variable, e.g. `CORS_ALLOWED_ORIGINS=`, means an empty list.
+        for list in [
+            &mut config.cors_allowed_origins,
+            &mut config.cors_allowed_methods,
+            &mut config.cors_allowed_headers,
+        ] {
+            list.retain(|entry| !entry.trim().is_empty());
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Rejects settings the server cannot start with, naming the variable to
+    /// fix.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+        if self.database_url.trim().is_empty() {
+            return invalid("DATABASE_URL must be set".into());
+        }
+        if !(self.database_url.starts_with("postgres://") || self.database_url.starts_with("postgresql://")) {
+            return invalid("DATABASE_URL must be a postgres:// URL".into());
+        }
+
+        match self.bind_address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => {
+                return invalid(format!(
+                    "BIND_ADDRESS must be host:port, got '{}'",
+                    self.bind_address
+                ))
+            }
+        }
+
+        if self.db_max_connections == 0 {
+            return invalid("DB_MAX_CONNECTIONS must be at least 1".into());
+        }
+        if self.db_min_connections > self.db_max_connections {
+            return invalid(format!(
+                "DB_MIN_CONNECTIONS ({}) must not exceed DB_MAX_CONNECTIONS ({})",
+                self.db_min_connections, self.db_max_connections
+            ));
+        }
+        for (name, value) in [
+            ("DB_ACQUIRE_TIMEOUT", self.db_acquire_timeout),
+            ("DB_IDLE_TIMEOUT", self.db_idle_timeout),
+            ("REQUEST_TIMEOUT", self.request_timeout),
+            ("HEALTH_CHECK_TIMEOUT", self.health_check_timeout),
+        ] {
+            if value == 0 {
+                return invalid(format!("{} must be a positive number of seconds", name));
+            }
         }
+        if self.db_connect_retries == 0 {
+            return invalid("DB_CONNECT_RETRIES must be at least 1".into());
+        }
+
+        cors::validate(self)?;
+
+        if self.jwt_secret.is_empty() {
+            return invalid("JWT_SECRET must be set".into());
+        }
+        if self.jwt_expiration <= 0 {
+            return invalid("JWT_EXPIRATION must be a positive number of seconds".into());
+        }
+        if self.jwt_expiration > MAX_JWT_EXPIRATION {
+            return invalid(format!(
+                "JWT_EXPIRATION must be at most {} seconds (one year)",
+                MAX_JWT_EXPIRATION
+            ));
+        }
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn db_acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.db_acquire_timeout)
+    }
+
+    pub fn db_idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.db_idle_timeout)
+    }
+
+    pub fn db_connect_retry_delay(&self) -> Duration {
+        Duration::from_secs(self.db_connect_retry_delay)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout)
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_secs(self.health_check_timeout)
+    }
+}
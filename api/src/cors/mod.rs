@@ -0,0 +1,248 @@
+use actix_cors::Cors;
+use actix_web::http::header::HeaderName;
+use actix_web::http::Method;
+use serde::Deserialize;
+
+use crate::config::{Config, ConfigError};
+
+/// Overall CORS policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorsMode {
+    /// Only the configured origins, methods and headers are allowed.
+    #[default]
+    Strict,
+    /// Any origin, method and header is allowed, with credentials. For local
+    /// development only.
+    Permissive,
+}
+
+/// An entry of `CORS_ALLOWED_ORIGINS`: `*` for any origin, an exact origin
+/// such as `https://dashboard.example.org`, or `https://*.example.org` for
+/// every subdomain of `example.org` (but not `example.org` itself).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    Any,
+    Exact(String),
+    /// The scheme with `://`, and the host suffix starting with a dot,
+    /// including any port.
+    Subdomains { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim().to_lowercase();
+        if pattern == "*" {
+            return Ok(Self::Any);
+        }
+
+        let invalid = || {
+            format!(
+                "'{}' is not an origin such as https://example.org or https://*.example.org",
+                pattern
+            )
+        };
+
+        let (scheme, host) = ["http://", "https://"]
+            .into_iter()
+            .find_map(|scheme| pattern.strip_prefix(scheme).map(|host| (scheme, host)))
+            .ok_or_else(invalid)?;
+
+        if host.is_empty() || host.contains('/') {
+            return Err(invalid());
+        }
+
+        match host.strip_prefix('*') {
+            Some(suffix) if suffix.len() > 1 && suffix.starts_with('.') && !suffix.contains('*') => {
+                Ok(Self::Subdomains {
+                    scheme: scheme.to_string(),
+                    suffix: suffix.to_string(),
+                })
+            }
+            Some(_) => Err(invalid()),
+            None if host.contains('*') => Err(invalid()),
+            None => Ok(Self::Exact(pattern.clone())),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_lowercase();
+        match self {
+            Self::Any => true,
+            Self::Exact(exact) => origin == *exact,
+            Self::Subdomains { scheme, suffix } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|host| host.strip_suffix(suffix.as_str()))
+                .is_some_and(|subdomain| {
+                    !subdomain.is_empty() && !subdomain.contains([':', '/']) && !subdomain.ends_with('.')
+                }),
+        }
+    }
+}
+
+/// Checks the CORS settings, so that `cors` can rely on them.
+pub fn validate(config: &Config) -> Result<(), ConfigError> {
+    let invalid = |message: String| ConfigError::Invalid(message);
+
+    let mut any_origin = false;
+    for origin in &config.cors_allowed_origins {
+        let pattern = OriginPattern::parse(origin).map_err(|e| invalid(format!("CORS_ALLOWED_ORIGINS: {}", e)))?;
+        any_origin |= pattern == OriginPattern::Any;
+    }
+
+    if any_origin && config.cors_allow_credentials && config.cors_mode == CorsMode::Strict {
+        return Err(invalid(
+            "CORS_ALLOW_CREDENTIALS cannot be combined with the * origin; list the origins instead".into(),
+        ));
+    }
+
+    if config.cors_allowed_methods.is_empty() {
+        return Err(invalid("CORS_ALLOWED_METHODS must not be empty".into()));
+    }
+    for method in &config.cors_allowed_methods {
+        Method::from_bytes(method.trim().to_uppercase().as_bytes())
+            .map_err(|_| invalid(format!("CORS_ALLOWED_METHODS: '{}' is not an HTTP method", method)))?;
+    }
+
+    for header in &config.cors_allowed_headers {
+        HeaderName::from_bytes(header.trim().as_bytes())
+            .map_err(|_| invalid(format!("CORS_ALLOWED_HEADERS: '{}' is not a header name", header)))?;
+    }
+
+    Ok(())
+}
+
+/// Builds the CORS middleware for one worker from validated settings.
+pub fn cors(config: &Config) -> Cors {
+    if config.cors_mode == CorsMode::Permissive {
+        return Cors::permissive();
+    }
+
+    let patterns: Vec<OriginPattern> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|origin| OriginPattern::parse(origin).ok())
+        .collect();
+
+    let mut cors = if patterns.contains(&OriginPattern::Any) {
+        Cors::default().allow_any_origin()
+    } else {
+        Cors::default().allowed_origin_fn(move |origin, _| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+        })
+    };
+
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.trim().to_uppercase().as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .cors_allowed_headers
+        .iter()
+        .filter_map(|header| HeaderName::from_bytes(header.trim().as_bytes()).ok())
+        .collect();
+
+    cors = cors
+        .allowed_methods(methods)
+        .allowed_headers(headers)
+        .max_age(config.cors_max_age);
+
+    if config.cors_allow_credentials {
+        cors = cors.supports_credentials();
+    }
+
+    cors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str], allow_credentials: bool) -> Config {
+        Config {
+            database_url: "postgres://localhost/pbtar".into(),
+            bind_address: "0.0.0.0:8080".into(),
+            db_max_connections: 10,
+            db_min_connections: 0,
+            db_acquire_timeout: 3,
+            db_idle_timeout: 600,
+            db_connect_retries: 10,
+            db_connect_retry_delay: 5,
+            request_timeout: 5,
+            shutdown_timeout: 30,
+            health_check_timeout: 2,
+            cors_mode: CorsMode::Strict,
+            cors_allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            cors_allowed_methods: vec!["GET".into(), "POST".into()],
+            cors_allowed_headers: vec!["Content-Type".into()],
+            cors_allow_credentials: allow_credentials,
+            cors_max_age: 3600,
+            jwt_secret: "secret".into(),
+            jwt_expiration: 86400,
+            log_format: Default::default(),
+            run_migrations: true,
+            seed_sample_data: false,
+        }
+    }
+
+    #[test]
+    fn matches_an_exact_origin() {
+        let pattern = OriginPattern::parse("https://dashboard.example.org").unwrap();
+        assert!(pattern.matches("https://dashboard.example.org"));
+        assert!(!pattern.matches("http://dashboard.example.org"));
+        assert!(!pattern.matches("https://other.example.org"));
+    }
+
+    #[test]
+    fn matches_subdomains_but_not_the_apex() {
+        let pattern = OriginPattern::parse("https://*.example.org").unwrap();
+        assert!(pattern.matches("https://dashboard.example.org"));
+        assert!(pattern.matches("https://a.b.example.org"));
+        assert!(!pattern.matches("https://example.org"));
+        assert!(!pattern.matches("https://.example.org"));
+    }
+
+    #[test]
+    fn rejects_lookalike_domains() {
+        let pattern = OriginPattern::parse("https://*.example.org").unwrap();
+        assert!(!pattern.matches("https://evilexample.org"));
+        assert!(!pattern.matches("https://dashboard.example.org.evil.com"));
+    }
+
+    #[test]
+    fn requires_the_same_port() {
+        let exact = OriginPattern::parse("http://localhost:5173").unwrap();
+        assert!(exact.matches("http://localhost:5173"));
+        assert!(!exact.matches("http://localhost:3000"));
+        assert!(!exact.matches("http://localhost"));
+
+        let subdomains = OriginPattern::parse("https://*.example.org:8443").unwrap();
+        assert!(subdomains.matches("https://app.example.org:8443"));
+        assert!(!subdomains.matches("https://app.example.org"));
+        assert!(!subdomains.matches("https://app.example.org:9443"));
+    }
+
+    #[test]
+    fn ignores_case() {
+        let pattern = OriginPattern::parse("HTTPS://*.Example.ORG").unwrap();
+        assert!(pattern.matches("https://dashboard.example.org"));
+        assert!(pattern.matches("https://Dashboard.EXAMPLE.org"));
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        for pattern in ["example.org", "ftp://example.org", "https://", "https://*", "https://a.*.example.org"] {
+            assert!(OriginPattern::parse(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn rejects_any_origin_with_credentials() {
+        assert!(validate(&config(&["*"], true)).is_err());
+        assert!(validate(&config(&["*"], false)).is_ok());
+        assert!(validate(&config(&["https://*.example.org"], true)).is_ok());
+    }
+}
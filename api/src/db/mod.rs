@@ -1,10 +1,118 @@
+use sqlx::migrate::{MigrateError, Migrator};
 use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::time::Duration;
+use sqlx::Executor;
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+use crate::config::Config;
+use crate::models::TemperatureTarget;
+
+pub mod scenarios;
+pub mod taxonomy;
+
+/// The schema migrations in `api/migrations`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Sample publishers, taxonomies, scenarios and pathway data.
+const SAMPLE_DATA: &str = include_str!("../../seeds/sample_data.sql");
+
+pub async fn create_pool(config: &Config) -> Result<PgPool, sqlx::Error> {
     PgPoolOptions::new()
-        .max_connections(10)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect(database_url)
+        .max_connections(config.db_max_connections)
+        .min_connections(config.db_min_connections)
+        .acquire_timeout(config.db_acquire_timeout())
+        .idle_timeout(config.db_idle_timeout())
+        .connect(&config.database_url)
+        .await
+}
+
+/// Applies every migration the database has not seen yet, then fills in
+/// the temperature bounds of scenarios stored before they existed.
+pub async fn migrate(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await?;
+    backfill_temperature_bounds(pool).await.map_err(MigrateError::Execute)
+}
+
+/// Parses the temperature target of scenarios with no bounds yet. The
+/// labels are parsed here rather than in SQL, so that existing scenarios
+/// get the same bounds as new ones. Modification times are left as is.
+async fn backfill_temperature_bounds(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let labels: Vec<(i32, String)> = sqlx::query_as(
+        r#"
+        SELECT id, temperature_target FROM pbtar.scenarios
+        WHERE temperature_target IS NOT NULL
+          AND temperature_lower IS NULL AND temperature_upper IS NULL
+          AND temperature_probability IS NULL AND temperature_overshoot IS NULL
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let parsed: Vec<(i32, TemperatureTarget)> = labels
+        .into_iter()
+        .map(|(id, label)| (id, TemperatureTarget::parse(&label)))
+        .filter(|(_, target)| *target != TemperatureTarget::default())
+        .collect();
+    if parsed.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    (&mut *tx).execute("ALTER TABLE pbtar.scenarios DISABLE TRIGGER update_scenarios_modtime").await?;
+    for (id, target) in parsed {
+        sqlx::query(
+            r#"
+            UPDATE pbtar.scenarios
+            SET temperature_lower = $2, temperature_upper = $3,
+                temperature_probability = $4, temperature_overshoot = $5
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(target.lower)
+        .bind(target.upper)
+        .bind(target.probability)
+        .bind(target.overshoot)
+        .execute(&mut *tx)
+        .await?;
+    }
+    (&mut *tx).execute("ALTER TABLE pbtar.scenarios ENABLE TRIGGER update_scenarios_modtime").await?;
+    tx.commit().await
+}
+
+/// The version of the newest migration applied to the database, or `None`
+/// if none was. A migration that failed part-way counts as not applied.
+pub async fn schema_version(pool: &PgPool) -> Result<Option<i64>, sqlx::Error> {
+    let applied: bool = sqlx::query_scalar("SELECT to_regclass('public._sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !applied {
+        return Ok(None);
+    }
+
+    sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+        .fetch_one(pool)
         .await
-}
\ No newline at end of file
+}
+
+/// The version of the newest migration built into this binary.
+pub fn expected_schema_version() -> Option<i64> {
+    MIGRATOR.iter().map(|migration| migration.version).max()
+}
+
+/// Loads the sample data into a database without scenarios. Returns whether
+/// it was loaded.
+pub async fn seed(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let empty = sqlx::query_scalar!(r#"SELECT NOT EXISTS(SELECT 1 FROM pbtar.scenarios) as "empty!""#)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !empty {
+        return Ok(false);
+    }
+
+    // Sent as one simple query, which may hold several statements.
+    (&mut *tx).execute(SAMPLE_DATA).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
@@ -0,0 +1,419 @@
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::models::{
+    CreateScenarioRequest, MatchMode, RevisionAction, ScenarioRelationInput, TemperatureTarget,
+};
+
+/// A many-to-many association between scenarios and one of the taxonomy tables.
+pub struct Association {
+    pub junction: &'static str,
+    pub column: &'static str,
+    pub target: &'static str,
+}
+
+pub const REGIONS: Association = Association {
+    junction: "scenario_regions",
+    column: "region_id",
+    target: "regions",
+};
+
+pub const STAKEHOLDERS: Association = Association {
+    junction: "scenario_stakeholders",
+    column: "stakeholder_id",
+    target: "stakeholders",
+};
+
+pub const SECTORS: Association = Association {
+    junction: "scenario_sectors",
+    column: "sector_id",
+    target: "sectors",
+};
+
+impl Association {
+    /// Rejects the request if any of `ids` does not exist in the target table.
+    pub async fn validate(&self, conn: &mut PgConnection, ids: &[i32]) -> Result<(), ApiError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!("SELECT id FROM pbtar.{} WHERE id = ANY($1)", self.target);
+        let found: Vec<i32> = sqlx::query_scalar(&sql)
+            .bind(ids)
+            .fetch_all(conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        let missing: Vec<String> = ids
+            .iter()
+            .filter(|id| !found.contains(id))
+            .map(|id| id.to_string())
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(ApiError::BadRequestError(format!(
+                "Unknown {} ids: {}",
+                self.target,
+                missing.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces every association of `scenario_id` with `ids`.
+    pub async fn replace(
+        &self,
+        conn: &mut PgConnection,
+        scenario_id: i32,
+        ids: &[i32],
+    ) -> Result<(), ApiError> {
+        let sql = format!("DELETE FROM pbtar.{} WHERE scenario_id = $1", self.junction);
+        sqlx::query(&sql)
+            .bind(scenario_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        let sql = format!(
+            "INSERT INTO pbtar.{} (scenario_id, {}) SELECT $1, UNNEST($2::int[]) ON CONFLICT DO NOTHING",
+            self.junction, self.column
+        );
+        sqlx::query(&sql)
+            .bind(scenario_id)
+            .bind(ids)
+            .execute(conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        Ok(())
+    }
+
+    /// Restricts the list to scenarios tagged with `ids`, combined per `mode`.
+    pub fn push_filter(&self, builder: &mut QueryBuilder<'_, Postgres>, ids: &[i32], mode: MatchMode) {
+        if ids.is_empty() {
+            return;
+        }
+
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        let required = ids.len() as i64;
+
+        builder
+            .push(format!(
+                " AND s.id IN (SELECT scenario_id FROM pbtar.{} WHERE {} = ANY(",
+                self.junction, self.column
+            ))
+            .push_bind(ids)
+            .push(")");
+
+        if mode == MatchMode::All {
+            builder
+                .push(format!(" GROUP BY scenario_id HAVING COUNT(DISTINCT {}) = ", self.column))
+                .push_bind(required);
+        }
+
+        builder.push(")");
+    }
+
+    /// Drops scenarios tagged with any of `ids`.
+    pub fn push_exclusion(&self, builder: &mut QueryBuilder<'_, Postgres>, ids: &[i32]) {
+        if ids.is_empty() {
+            return;
+        }
+
+        builder
+            .push(format!(
+                " AND s.id NOT IN (SELECT scenario_id FROM pbtar.{} WHERE {} = ANY(",
+                self.junction, self.column
+            ))
+            .push_bind(ids.to_vec())
+            .push("))");
+    }
+}
+
+/// Longest values the `VARCHAR` columns of `scenarios` accept.
+pub const TITLE_MAX_LENGTH: usize = 255;
+pub const TYPE_MAX_LENGTH: usize = 50;
+pub const MODEL_MAX_LENGTH: usize = 255;
+pub const TEMPERATURE_TARGET_MAX_LENGTH: usize = 50;
+
+pub fn validate_required(field: &str, value: &str) -> Result<(), ApiError> {
+    if value.trim().is_empty() {
+        return Err(ApiError::BadRequestError(format!("{} must not be empty", field)));
+    }
+    Ok(())
+}
+
+/// The complaint about `value` not fitting a column of `max` characters, if
+/// it does not. Surrounding whitespace is ignored, as it is trimmed on write.
+pub fn length_error(field: &str, value: &str, max: usize) -> Option<String> {
+    (value.trim().chars().count() > max).then(|| format!("{} must be at most {} characters", field, max))
+}
+
+pub fn validate_length(field: &str, value: &str, max: usize) -> Result<(), ApiError> {
+    match length_error(field, value, max) {
+        Some(message) => Err(ApiError::BadRequestError(message)),
+        None => Ok(()),
+    }
+}
+
+/// Checks the lengths of the scenario columns that have a limit.
+pub fn validate_lengths(
+    title: Option<&str>,
+    type_name: Option<&str>,
+    model: Option<&str>,
+    temperature_target: Option<&str>,
+) -> Result<(), ApiError> {
+    for (field, value, max) in [
+        ("title", title, TITLE_MAX_LENGTH),
+        ("type_name", type_name, TYPE_MAX_LENGTH),
+        ("model", model, MODEL_MAX_LENGTH),
+        ("temperature_target", temperature_target, TEMPERATURE_TARGET_MAX_LENGTH),
+    ] {
+        if let Some(value) = value {
+            validate_length(field, value, max)?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn validate_publisher(conn: &mut PgConnection, publisher_id: Option<i32>) -> Result<(), ApiError> {
+    let Some(publisher_id) = publisher_id else {
+        return Ok(());
+    };
+
+    sqlx::query!("SELECT id FROM pbtar.publishers WHERE id = $1", publisher_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::BadRequestError(format!("Unknown publisher id: {}", publisher_id)))?;
+
+    Ok(())
+}
+
+/// Checks the fields and references of a full scenario payload.
+pub async fn validate_scenario(conn: &mut PgConnection, payload: &CreateScenarioRequest) -> Result<(), ApiError> {
+    validate_required("title", &payload.title)?;
+    validate_required("type_name", &payload.type_name)?;
+    validate_lengths(
+        Some(&payload.title),
+        Some(&payload.type_name),
+        payload.model.as_deref(),
+        payload.temperature_target.as_deref(),
+    )?;
+
+    validate_publisher(&mut *conn, payload.publisher_id).await?;
+    REGIONS.validate(&mut *conn, &payload.region_ids).await?;
+    STAKEHOLDERS.validate(&mut *conn, &payload.stakeholder_ids).await?;
+    SECTORS.validate(&mut *conn, &payload.sector_ids).await?;
+
+    Ok(())
+}
+
+/// Validates and inserts a scenario with its associations, returning its id.
+pub async fn insert_scenario(conn: &mut PgConnection, payload: &CreateScenarioRequest) -> Result<i32, ApiError> {
+    validate_scenario(&mut *conn, payload).await?;
+
+    let temperature = payload
+        .temperature_target
+        .as_deref()
+        .map(TemperatureTarget::parse)
+        .unwrap_or_default();
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO pbtar.scenarios
+            (title, type, temperature_target, temperature_lower, temperature_upper,
+             temperature_probability, temperature_overshoot, description, publisher_id,
+             published_date, target_year, model)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING id
+        "#,
+        payload.title.trim(),
+        payload.type_name.trim(),
+        payload.temperature_target.as_deref().map(str::trim),
+        temperature.lower,
+        temperature.upper,
+        temperature.probability,
+        temperature.overshoot,
+        payload.description.as_deref().map(str::trim),
+        payload.publisher_id,
+        payload.published_date,
+        payload.target_year,
+        payload.model.as_deref().map(str::trim)
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    REGIONS.replace(&mut *conn, id, &payload.region_ids).await?;
+    STAKEHOLDERS.replace(&mut *conn, id, &payload.stakeholder_ids).await?;
+    SECTORS.replace(&mut *conn, id, &payload.sector_ids).await?;
+    if let Some(relations) = &payload.relations {
+        replace_relations(&mut *conn, id, relations).await?;
+    }
+
+    Ok(id)
+}
+
+/// Validates and overwrites a scenario and all of its associations.
+pub async fn replace_scenario(
+    conn: &mut PgConnection,
+    id: i32,
+    payload: &CreateScenarioRequest,
+) -> Result<(), ApiError> {
+    validate_scenario(&mut *conn, payload).await?;
+
+    let temperature = payload
+        .temperature_target
+        .as_deref()
+        .map(TemperatureTarget::parse)
+        .unwrap_or_default();
+
+    sqlx::query_scalar!(
+        r#"
+        UPDATE pbtar.scenarios
+        SET title = $2, type = $3, temperature_target = $4, temperature_lower = $5,
+            temperature_upper = $6, temperature_probability = $7, temperature_overshoot = $8,
+            description = $9, publisher_id = $10, published_date = $11, target_year = $12,
+            model = $13
+        WHERE id = $1
+        RETURNING id
+        "#,
+        id,
+        payload.title.trim(),
+        payload.type_name.trim(),
+        payload.temperature_target.as_deref().map(str::trim),
+        temperature.lower,
+        temperature.upper,
+        temperature.probability,
+        temperature.overshoot,
+        payload.description.as_deref().map(str::trim),
+        payload.publisher_id,
+        payload.published_date,
+        payload.target_year,
+        payload.model.as_deref().map(str::trim)
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Scenario with id {} not found", id)))?;
+
+    REGIONS.replace(&mut *conn, id, &payload.region_ids).await?;
+    STAKEHOLDERS.replace(&mut *conn, id, &payload.stakeholder_ids).await?;
+    SECTORS.replace(&mut *conn, id, &payload.sector_ids).await?;
+    if let Some(relations) = &payload.relations {
+        replace_relations(&mut *conn, id, relations).await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the relations held by scenario `id`. Relations other scenarios
+/// hold to it are left alone. Rejects unknown or self-referencing targets and
+/// chains of `supersedes` that loop back to `id`.
+pub async fn replace_relations(
+    conn: &mut PgConnection,
+    id: i32,
+    relations: &[ScenarioRelationInput],
+) -> Result<(), ApiError> {
+    if relations.iter().any(|r| r.scenario_id == id) {
+        return Err(ApiError::BadRequestError("A scenario cannot be related to itself".into()));
+    }
+
+    let targets: Vec<i32> = relations.iter().map(|r| r.scenario_id).collect();
+    let found = sqlx::query_scalar!("SELECT id FROM pbtar.scenarios WHERE id = ANY($1)", &targets)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(ApiError::DbError)?;
+    let missing: Vec<String> = targets
+        .iter()
+        .filter(|id| !found.contains(id))
+        .map(|id| id.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(ApiError::BadRequestError(format!(
+            "Unknown related scenario ids: {}",
+            missing.join(", ")
+        )));
+    }
+
+    sqlx::query!("DELETE FROM pbtar.scenario_relations WHERE scenario_id = $1", id)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    let kinds: Vec<String> = relations.iter().map(|r| r.relation.as_str().to_string()).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO pbtar.scenario_relations (scenario_id, related_scenario_id, relation)
+        SELECT $1, UNNEST($2::int[]), UNNEST($3::varchar[])
+        ON CONFLICT DO NOTHING
+        "#,
+        id,
+        &targets,
+        &kinds
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let cyclic = sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE superseded AS (
+            SELECT related_scenario_id AS id
+            FROM pbtar.scenario_relations
+            WHERE scenario_id = $1 AND relation = 'supersedes'
+            UNION
+            SELECT r.related_scenario_id
+            FROM pbtar.scenario_relations r
+            JOIN superseded ON r.scenario_id = superseded.id
+            WHERE r.relation = 'supersedes'
+        )
+        SELECT EXISTS (SELECT 1 FROM superseded WHERE id = $1) as "cyclic!"
+        "#,
+        id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    if cyclic {
+        return Err(ApiError::BadRequestError(
+            "Scenario editions cannot supersede each other in a cycle".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Records the current state of scenario `id` as its next revision. Call it in
+/// the same transaction as the change, after the change for creates and
+/// updates and before it for deletes.
+pub async fn record_revision(
+    conn: &mut PgConnection,
+    id: i32,
+    action: RevisionAction,
+    editor: Option<&Claims>,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO pbtar.scenario_revisions (scenario_id, revision, action, snapshot, user_id, username)
+        SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, pbtar.scenario_snapshot($1), $3, $4
+        FROM pbtar.scenario_revisions
+        WHERE scenario_id = $1
+        "#,
+        id,
+        action as RevisionAction,
+        editor.map(|claims| claims.sub),
+        editor.map(|claims| claims.username.as_str())
+    )
+    .execute(conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use sqlx::PgConnection;
+
+use crate::auth::Claims;
+use crate::errors::ApiError;
+use crate::models::RevisionAction;
+
+use super::scenarios::{self, record_revision, validate_length, validate_required, Association};
+
+/// Longest name the taxonomy tables store.
+pub const NAME_MAX_LENGTH: usize = 100;
+
+/// Longest stakeholder type the database stores.
+pub const STAKEHOLDER_TYPE_MAX_LENGTH: usize = 50;
+
+/// How scenarios refer to the entries of a taxonomy table.
+pub enum Usage {
+    /// Through a column of `scenarios`.
+    Column(&'static str),
+    /// Through a junction table.
+    Junction(&'static Association),
+}
+
+/// One of the taxonomy tables scenarios are described with.
+pub struct Taxonomy {
+    pub table: &'static str,
+    pub label: &'static str,
+    pub usage: Usage,
+    /// The column of `timeseries` referring to entries, if pathway data does.
+    pub timeseries_column: Option<&'static str>,
+}
+
+pub const PUBLISHERS: Taxonomy = Taxonomy {
+    table: "publishers",
+    label: "Publisher",
+    usage: Usage::Column("publisher_id"),
+    timeseries_column: None,
+};
+
+pub const REGIONS: Taxonomy = Taxonomy {
+    table: "regions",
+    label: "Region",
+    usage: Usage::Junction(&scenarios::REGIONS),
+    timeseries_column: Some("region_id"),
+};
+
+pub const STAKEHOLDERS: Taxonomy = Taxonomy {
+    table: "stakeholders",
+    label: "Stakeholder",
+    usage: Usage::Junction(&scenarios::STAKEHOLDERS),
+    timeseries_column: None,
+};
+
+pub const SECTORS: Taxonomy = Taxonomy {
+    table: "sectors",
+    label: "Sector",
+    usage: Usage::Junction(&scenarios::SECTORS),
+    timeseries_column: Some("sector_id"),
+};
+
+impl Taxonomy {
+    /// Rejects an empty or overlong `name`, or one already used by an entry
+    /// other than `except`. Names are compared case-insensitively, as imports
+    /// match them.
+    pub async fn validate_name(&self, conn: &mut PgConnection, name: &str, except: Option<i32>) -> Result<(), ApiError> {
+        validate_required("name", name)?;
+        validate_length("name", name, NAME_MAX_LENGTH)?;
+
+        let sql = format!(
+            "SELECT EXISTS(SELECT 1 FROM pbtar.{} WHERE lower(name) = lower($1) AND id IS DISTINCT FROM $2)",
+            self.table
+        );
+        let taken: bool = sqlx::query_scalar(&sql)
+            .bind(name.trim())
+            .bind(except)
+            .fetch_one(conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        if taken {
+            return Err(ApiError::ConflictError(format!(
+                "{} '{}' already exists",
+                self.label,
+                name.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Maps an error writing an entry named `name` to a conflict when the
+    /// name is taken, as another request may have taken it since
+    /// `validate_name` checked.
+    pub fn write_error(&self, name: &str, e: sqlx::Error) -> ApiError {
+        match e.as_database_error().and_then(|db| db.code()) {
+            Some(code) if code == "23505" => {
+                ApiError::ConflictError(format!("{} '{}' already exists", self.label, name.trim()))
+            }
+            _ => ApiError::DbError(e),
+        }
+    }
+
+    /// Locks entry `id` for the rest of the transaction.
+    pub async fn lock(&self, conn: &mut PgConnection, id: i32) -> Result<(), ApiError> {
+        let sql = format!("SELECT id FROM pbtar.{} WHERE id = $1 FOR UPDATE", self.table);
+        sqlx::query_scalar::<_, i32>(&sql)
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+            .map_err(ApiError::DbError)?
+            .ok_or_else(|| ApiError::NotFoundError(format!("{} with id {} not found", self.label, id)))?;
+
+        Ok(())
+    }
+
+    /// Ids of the scenarios referring to entry `id`.
+    async fn scenario_ids(&self, conn: &mut PgConnection, id: i32) -> Result<Vec<i32>, ApiError> {
+        let sql = match self.usage {
+            Usage::Column(column) => format!("SELECT id FROM pbtar.scenarios WHERE {} = $1 ORDER BY id", column),
+            Usage::Junction(association) => format!(
+                "SELECT scenario_id FROM pbtar.{} WHERE {} = $1 ORDER BY scenario_id",
+                association.junction, association.column
+            ),
+        };
+
+        sqlx::query_scalar(&sql)
+            .bind(id)
+            .fetch_all(conn)
+            .await
+            .map_err(ApiError::DbError)
+    }
+
+    /// Number of time-series values referring to entry `id`.
+    async fn timeseries_count(&self, conn: &mut PgConnection, id: i32) -> Result<i64, ApiError> {
+        let Some(column) = self.timeseries_column else {
+            return Ok(0);
+        };
+
+        let sql = format!("SELECT COUNT(*) FROM pbtar.timeseries WHERE {} = $1", column);
+        sqlx::query_scalar(&sql)
+            .bind(id)
+            .fetch_one(conn)
+            .await
+            .map_err(ApiError::DbError)
+    }
+
+    /// Points every scenario and time-series value referring to `from` at `to`.
+    async fn reassign(&self, conn: &mut PgConnection, from: i32, to: i32) -> Result<(), ApiError> {
+        // Junction rows of `from` go with the entry, by ON DELETE CASCADE.
+        let sql = match self.usage {
+            Usage::Column(column) => format!("UPDATE pbtar.scenarios SET {0} = $2 WHERE {0} = $1", column),
+            Usage::Junction(association) => format!(
+                "INSERT INTO pbtar.{0} (scenario_id, {1}) SELECT scenario_id, $2 FROM pbtar.{0} WHERE {1} = $1 ON CONFLICT DO NOTHING",
+                association.junction, association.column
+            ),
+        };
+        sqlx::query(&sql)
+            .bind(from)
+            .bind(to)
+            .execute(&mut *conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        if let Some(column) = self.timeseries_column {
+            let sql = format!("UPDATE pbtar.timeseries SET {0} = $2 WHERE {0} = $1", column);
+            sqlx::query(&sql)
+                .bind(from)
+                .bind(to)
+                .execute(conn)
+                .await
+                .map_err(|e| match e.as_database_error().and_then(|db| db.code()) {
+                    Some(code) if code == "23505" => ApiError::ConflictError(format!(
+                        "Pathway data of {} {} overlaps with that of {} {}",
+                        self.table, from, self.table, to
+                    )),
+                    _ => ApiError::DbError(e),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes entry `id`. Scenarios and pathway data referring to it are
+    /// moved to `reassign_to`, recording a revision of each scenario;
+    /// without a reassignment target, deleting an entry in use is refused.
+    pub async fn delete(
+        &self,
+        conn: &mut PgConnection,
+        id: i32,
+        reassign_to: Option<i32>,
+        editor: &Claims,
+    ) -> Result<(), ApiError> {
+        self.lock(conn, id).await?;
+
+        if let Some(target) = reassign_to {
+            if target == id {
+                return Err(ApiError::BadRequestError(format!(
+                    "Cannot reassign {} {} to itself",
+                    self.label, id
+                )));
+            }
+            self.lock(conn, target).await.map_err(|e| match e {
+                ApiError::NotFoundError(_) => {
+                    ApiError::BadRequestError(format!("Unknown {} id to reassign to: {}", self.table, target))
+                }
+                e => e,
+            })?;
+        }
+
+        let scenario_ids = self.scenario_ids(conn, id).await?;
+        let values = self.timeseries_count(conn, id).await?;
+
+        match reassign_to {
+            Some(target) => self.reassign(conn, id, target).await?,
+            None if !scenario_ids.is_empty() || values > 0 => {
+                let mut uses = Vec::new();
+                if !scenario_ids.is_empty() {
+                    uses.push(format!("{} scenario(s)", scenario_ids.len()));
+                }
+                if values > 0 {
+                    uses.push(format!("{} time-series value(s)", values));
+                }
+                return Err(ApiError::ConflictError(format!(
+                    "{} {} is used by {}; supply reassign_to to move them",
+                    self.label,
+                    id,
+                    uses.join(" and ")
+                )));
+            }
+            None => {}
+        }
+
+        let sql = format!("DELETE FROM pbtar.{} WHERE id = $1", self.table);
+        sqlx::query(&sql)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        for scenario_id in scenario_ids {
+            record_revision(conn, scenario_id, RevisionAction::Update, Some(editor)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Locks the regions table against other writes for the rest of the
+/// transaction, so that concurrent moves cannot close a cycle between them.
+/// Take it before locking any region, or two moves may deadlock.
+pub async fn lock_regions(conn: &mut PgConnection) -> Result<(), ApiError> {
+    sqlx::query("LOCK TABLE pbtar.regions IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(())
+}
+
+/// Rejects a `parent_id` for region `id` (`None` for a new region) that does
+/// not exist, or that is the region itself or one of its subregions. Call it
+/// with the regions locked by `lock_regions`.
+pub async fn validate_region_parent(
+    conn: &mut PgConnection,
+    id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    if Some(parent_id) == id {
+        return Err(ApiError::BadRequestError("A region cannot be its own parent".into()));
+    }
+
+    // Walks up from the new parent; reaching the region would close a cycle.
+    let ancestors = sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id FROM pbtar.regions WHERE id = $1
+            UNION
+            SELECT r.id, r.parent_id FROM pbtar.regions r JOIN ancestors a ON r.id = a.parent_id
+        )
+        SELECT id as "id!" FROM ancestors
+        "#,
+        parent_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    if ancestors.is_empty() {
+        return Err(ApiError::BadRequestError(format!("Unknown parent region id: {}", parent_id)));
+    }
+
+    if let Some(id) = id {
+        if ancestors.contains(&id) {
+            return Err(ApiError::BadRequestError(format!(
+                "Region {} is a subregion of region {} and cannot be its parent",
+                parent_id, id
+            )));
+        }
+    }
+
+    Ok(())
+}
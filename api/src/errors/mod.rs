@@ -15,6 +15,9 @@ pub enum ApiError {
     
     #[error("Bad request: {0}")]
     BadRequestError(String),
+
+    #[error("Conflict: {0}")]
+    ConflictError(String),
     
     #[error("Internal server error: {0}")]
     InternalError(String),
@@ -47,6 +50,7 @@ impl ResponseError for ApiError {
             ApiError::ForbiddenError(_) => StatusCode::FORBIDDEN,
             ApiError::NotFoundError(_) => StatusCode::NOT_FOUND,
             ApiError::BadRequestError(_) => StatusCode::BAD_REQUEST,
+            ApiError::ConflictError(_) => StatusCode::CONFLICT,
             ApiError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
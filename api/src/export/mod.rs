@@ -0,0 +1,300 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::errors::ApiError;
+use crate::models::{IamcLayout, IamcValue, ScenarioExportRow};
+
+/// Column headers, named after the import columns so that an export can be
+/// edited and imported again.
+const HEADERS: [&str; 15] = [
+    "id",
+    "title",
+    "type",
+    "temperature_target",
+    "temperature_lower",
+    "temperature_upper",
+    "temperature_probability",
+    "temperature_overshoot",
+    "description",
+    "publisher",
+    "published_date",
+    "target_year",
+    "regions",
+    "sectors",
+    "stakeholders",
+];
+
+/// Most scenarios an XLSX export may hold, as the workbook is built in
+/// memory. CSV exports are streamed and have no limit.
+pub const MAX_XLSX_ROWS: i64 = 10_000;
+
+/// Characters that make spreadsheet applications read a CSV cell as a
+/// formula.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Prefixes CSV text that spreadsheet applications would evaluate as a
+/// formula with `'`, so that it is shown as text. XLSX string cells are
+/// never evaluated and are written as is.
+fn escape_formula(value: &str) -> Cow<'_, str> {
+    if value.starts_with(FORMULA_PREFIXES) {
+        Cow::Owned(format!("'{}", value))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Undoes `escape_formula`, so that an exported CSV imports unchanged.
+pub fn unescape_formula(value: &str) -> &str {
+    match value.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_PREFIXES) => rest,
+        _ => value,
+    }
+}
+
+fn text<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+fn escaped_text(value: &Option<String>) -> String {
+    value.as_deref().map(escape_formula).unwrap_or_default().into_owned()
+}
+
+fn csv_line<I, T>(record: I) -> Result<Vec<u8>, ApiError>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(record)
+        .map_err(|e| ApiError::InternalError(format!("Failed to write CSV: {}", e)))?;
+
+    writer
+        .into_inner()
+        .map_err(|e| ApiError::InternalError(format!("Failed to write CSV: {}", e)))
+}
+
+/// The header line of a scenario CSV export.
+pub fn scenarios_csv_header() -> Result<Vec<u8>, ApiError> {
+    csv_line(HEADERS)
+}
+
+/// The line of one scenario in a CSV export.
+pub fn scenarios_csv_record(row: &ScenarioExportRow) -> Result<Vec<u8>, ApiError> {
+    csv_line([
+        row.id.to_string(),
+        escape_formula(&row.title).into_owned(),
+        escape_formula(&row.type_name).into_owned(),
+        escaped_text(&row.temperature_target),
+        text(&row.temperature_lower),
+        text(&row.temperature_upper),
+        text(&row.temperature_probability),
+        text(&row.temperature_overshoot),
+        escaped_text(&row.description),
+        escaped_text(&row.publisher),
+        text(&row.published_date),
+        text(&row.target_year),
+        escape_formula(&row.regions).into_owned(),
+        escape_formula(&row.sectors).into_owned(),
+        escape_formula(&row.stakeholders).into_owned(),
+    ])
+}
+
+pub fn scenarios_xlsx(rows: &[ScenarioExportRow]) -> Result<Vec<u8>, ApiError> {
+    write_xlsx(rows).map_err(|e| ApiError::InternalError(format!("Failed to write XLSX: {}", e)))
+}
+
+fn write_xlsx(rows: &[ScenarioExportRow]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Scenarios")?;
+
+    let header = Format::new().set_bold();
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+
+    sheet.write_row_with_format(0, 0, HEADERS, &header)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (index, row) in rows.iter().enumerate() {
+        let r = index as u32 + 1;
+
+        sheet.write_number(r, 0, row.id)?;
+        sheet.write_string(r, 1, &row.title)?;
+        sheet.write_string(r, 2, &row.type_name)?;
+        write_opt_string(sheet, r, 3, &row.temperature_target)?;
+        write_opt_number(sheet, r, 4, row.temperature_lower)?;
+        write_opt_number(sheet, r, 5, row.temperature_upper)?;
+        write_opt_number(sheet, r, 6, row.temperature_probability)?;
+        if let Some(overshoot) = row.temperature_overshoot {
+            sheet.write_boolean(r, 7, overshoot)?;
+        }
+        write_opt_string(sheet, r, 8, &row.description)?;
+        write_opt_string(sheet, r, 9, &row.publisher)?;
+        if let Some(published) = row.published_date {
+            sheet.write_datetime_with_format(r, 10, published, &date)?;
+        }
+        write_opt_number(sheet, r, 11, row.target_year.map(f64::from))?;
+        sheet.write_string(r, 12, &row.regions)?;
+        sheet.write_string(r, 13, &row.sectors)?;
+        sheet.write_string(r, 14, &row.stakeholders)?;
+    }
+
+    sheet.autofilter(0, 0, rows.len() as u32, HEADERS.len() as u16 - 1)?;
+    sheet.autofit();
+
+    workbook.save_to_buffer()
+}
+
+fn write_opt_string(sheet: &mut Worksheet, row: u32, col: u16, value: &Option<String>) -> Result<(), XlsxError> {
+    if let Some(value) = value {
+        sheet.write_string(row, col, value)?;
+    }
+    Ok(())
+}
+
+fn write_opt_number(sheet: &mut Worksheet, row: u32, col: u16, value: Option<f64>) -> Result<(), XlsxError> {
+    if let Some(value) = value {
+        sheet.write_number(row, col, value)?;
+    }
+    Ok(())
+}
+
+const IAMC_INDEX: [&str; 5] = ["Model", "Scenario", "Region", "Variable", "Unit"];
+
+/// Writes values, sorted by model, scenario (title, then id), region,
+/// variable, unit and year, as an IAMC table in the given layout.
+///
+/// Scenarios sharing a model and title are labelled `"{title} ({id})"` so
+/// that tools reading the table keep them apart. A sector series whose
+/// `Variable|Sector` name is also the name of a variable with data in the
+/// same scenario, region and unit cannot be told apart from it, and is
+/// rejected as a conflict.
+pub fn timeseries_iamc_csv(values: &[IamcValue], layout: IamcLayout) -> Result<Vec<u8>, ApiError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let internal = |e: csv::Error| ApiError::InternalError(format!("Failed to write CSV: {}", e));
+
+    let same_value = |a: &IamcValue, b: &IamcValue| {
+        (a.scenario_id, &a.region, &a.variable, &a.unit, a.year)
+            == (b.scenario_id, &b.region, &b.variable, &b.unit, b.year)
+    };
+    if let Some(pair) = values.windows(2).find(|pair| same_value(&pair[0], &pair[1])) {
+        return Err(ApiError::ConflictError(format!(
+            "Scenario {} has more than one series exported as '{}' in region '{}'; \
+             a sector series clashes with a variable of that name, so export them separately \
+             with sector_id or variable",
+            pair[0].scenario_id, pair[0].variable, pair[0].region
+        )));
+    }
+
+    let mut scenario_ids: HashMap<(&str, &str), BTreeSet<i32>> = HashMap::new();
+    for v in values {
+        scenario_ids.entry((&v.model, &v.scenario)).or_default().insert(v.scenario_id);
+    }
+    let index = |v: &IamcValue| {
+        let scenario = if scenario_ids[&(v.model.as_str(), v.scenario.as_str())].len() > 1 {
+            format!("{} ({})", v.scenario, v.scenario_id)
+        } else {
+            v.scenario.clone()
+        };
+        vec![v.model.clone(), scenario, v.region.clone(), v.variable.clone(), v.unit.clone()]
+    };
+
+    match layout {
+        IamcLayout::Long => {
+            let mut headers = IAMC_INDEX.map(String::from).to_vec();
+            headers.extend(["Year".to_string(), "Value".to_string()]);
+            writer.write_record(&headers).map_err(internal)?;
+
+            for v in values {
+                let mut record = index(v);
+                record.extend([v.year.to_string(), v.value.to_string()]);
+                writer.write_record(&record).map_err(internal)?;
+            }
+        }
+        IamcLayout::Wide => {
+            let years: Vec<i32> = values.iter().map(|v| v.year).collect::<BTreeSet<_>>().into_iter().collect();
+
+            let mut headers = IAMC_INDEX.map(String::from).to_vec();
+            headers.extend(years.iter().map(i32::to_string));
+            writer.write_record(&headers).map_err(internal)?;
+
+            let same_series = |a: &IamcValue, b: &IamcValue| {
+                (a.scenario_id, &a.region, &a.variable, &a.unit) == (b.scenario_id, &b.region, &b.variable, &b.unit)
+            };
+
+            for series in values.chunk_by(same_series) {
+                let mut record = index(&series[0]);
+                record.extend(years.iter().map(|year| {
+                    series
+                        .iter()
+                        .find(|v| v.year == *year)
+                        .map(|v| v.value.to_string())
+                        .unwrap_or_default()
+                }));
+                writer.write_record(&record).map_err(internal)?;
+            }
+        }
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| ApiError::InternalError(format!("Failed to write CSV: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_formula_like_text() {
+        assert_eq!(escape_formula("=SUM(A1:A9)"), "'=SUM(A1:A9)");
+        assert_eq!(escape_formula("+2°C"), "'+2°C");
+        assert_eq!(escape_formula("1.5°C"), "1.5°C");
+    }
+
+    #[test]
+    fn unescapes_what_it_escaped() {
+        for value in ["=SUM(A1:A9)", "- bullet", "@home", "'quoted", "plain"] {
+            assert_eq!(unescape_formula(&escape_formula(value)), value);
+        }
+    }
+
+    fn iamc_value(scenario_id: i32, title: &str, variable: &str, year: i32) -> IamcValue {
+        IamcValue {
+            model: "WEO".into(),
+            scenario: title.into(),
+            region: "World".into(),
+            variable: variable.into(),
+            unit: "Mt CO2/yr".into(),
+            year,
+            value: 1.0,
+            scenario_id,
+        }
+    }
+
+    #[test]
+    fn labels_scenarios_sharing_a_title_with_their_id() {
+        let values = [
+            iamc_value(1, "Net Zero", "Emissions|CO2", 2030),
+            iamc_value(2, "Net Zero", "Emissions|CO2", 2030),
+            iamc_value(3, "Stated Policies", "Emissions|CO2", 2030),
+        ];
+        let csv = String::from_utf8(timeseries_iamc_csv(&values, IamcLayout::Long).unwrap()).unwrap();
+        let scenarios: Vec<&str> = csv.lines().skip(1).map(|line| line.split(',').nth(1).unwrap()).collect();
+        assert_eq!(scenarios, ["Net Zero (1)", "Net Zero (2)", "Stated Policies"]);
+    }
+
+    #[test]
+    fn rejects_sector_series_clashing_with_a_variable() {
+        let values = [
+            iamc_value(1, "Net Zero", "Emissions|CO2|Power", 2030),
+            iamc_value(1, "Net Zero", "Emissions|CO2|Power", 2030),
+        ];
+        assert!(matches!(
+            timeseries_iamc_csv(&values, IamcLayout::Wide),
+            Err(ApiError::ConflictError(_))
+        ));
+    }
+}
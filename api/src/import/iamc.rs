@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use calamine::{open_workbook_from_rs, Reader, Xlsx};
+use sqlx::{PgConnection, PgPool};
+
+use crate::db::scenarios::length_error;
+use crate::db::taxonomy;
+use crate::errors::ApiError;
+use crate::models::{IamcImportReport, IamcRowError, IamcScenarioMatch};
+
+use super::NameIndex;
+
+/// Longest names the database stores for regions, variables and units.
+const REGION_MAX_LENGTH: usize = taxonomy::NAME_MAX_LENGTH;
+const VARIABLE_MAX_LENGTH: usize = 255;
+const UNIT_MAX_LENGTH: usize = 50;
+
+/// Reads the first sheet of an XLSX workbook, or a CSV file, into rows of
+/// trimmed cells. Workbooks are recognised by their zip signature.
+fn read_table(data: &[u8]) -> Result<Vec<Vec<String>>, ApiError> {
+    if data.starts_with(b"PK\x03\x04") {
+        let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(data))
+            .map_err(|e| ApiError::BadRequestError(format!("Invalid XLSX file: {}", e)))?;
+        let range = workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| ApiError::BadRequestError("The workbook has no sheets".into()))?
+            .map_err(|e| ApiError::BadRequestError(format!("Invalid XLSX file: {}", e)))?;
+
+        return Ok(range
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.to_string().trim().to_string()).collect())
+            .collect());
+    }
+
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data)
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(|cell| cell.trim().to_string()).collect())
+                .map_err(|e| ApiError::BadRequestError(format!("Invalid CSV: {}", e)))
+        })
+        .collect()
+}
+
+/// Column positions of an IAMC wide-format table.
+struct Columns {
+    model: usize,
+    scenario: usize,
+    region: usize,
+    variable: usize,
+    unit: usize,
+    /// Every column whose header is a year, with that year.
+    years: Vec<(usize, i32)>,
+}
+
+impl Columns {
+    fn from_headers(headers: &[String]) -> Result<Self, ApiError> {
+        let require = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(name))
+                .ok_or_else(|| ApiError::BadRequestError(format!("Missing required column '{}'", name)))
+        };
+
+        let mut years: Vec<(usize, i32)> = Vec::new();
+        for (index, header) in headers.iter().enumerate() {
+            let Ok(year) = header.parse::<i32>() else {
+                continue;
+            };
+            if years.iter().any(|(_, existing)| *existing == year) {
+                return Err(ApiError::BadRequestError(format!("Duplicate year column '{}'", year)));
+            }
+            years.push((index, year));
+        }
+        if years.is_empty() {
+            return Err(ApiError::BadRequestError("No year columns found".into()));
+        }
+
+        Ok(Self {
+            model: require("Model")?,
+            scenario: require("Scenario")?,
+            region: require("Region")?,
+            variable: require("Variable")?,
+            unit: require("Unit")?,
+            years,
+        })
+    }
+}
+
+/// Looks up, and caches, the scenario a `Model`/`Scenario` pair refers to:
+/// the scenario with that title and model, or else one with that title and no
+/// model recorded.
+async fn find_scenario(
+    conn: &mut PgConnection,
+    cache: &mut HashMap<(String, String), Option<i32>>,
+    model: &str,
+    scenario: &str,
+) -> Result<Option<i32>, ApiError> {
+    let key = (model.to_lowercase(), scenario.to_lowercase());
+    if let Some(id) = cache.get(&key) {
+        return Ok(*id);
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM pbtar.scenarios
+        WHERE lower(title) = lower($1) AND (lower(model) = lower($2) OR model IS NULL)
+        ORDER BY model IS NULL, id
+        LIMIT 1
+        "#,
+        scenario,
+        model
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    cache.insert(key, id);
+    Ok(id)
+}
+
+/// Variables by lowercased name, with their id and unit, creating variables
+/// and units on first use.
+struct Variables {
+    variables: HashMap<String, (i32, String)>,
+    units: HashMap<String, i32>,
+}
+
+impl Variables {
+    async fn load(conn: &mut PgConnection) -> Result<Self, ApiError> {
+        let variables = sqlx::query!(
+            r#"
+            SELECT v.id, v.name, u.name as unit
+            FROM pbtar.variables v
+            JOIN pbtar.units u ON u.id = v.unit_id
+            "#
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(ApiError::DbError)?;
+
+        let units = sqlx::query!("SELECT id, name FROM pbtar.units")
+            .fetch_all(conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        Ok(Self {
+            variables: variables
+                .into_iter()
+                .map(|v| (v.name.to_lowercase(), (v.id, v.unit)))
+                .collect(),
+            units: units.into_iter().map(|u| (u.name, u.id)).collect(),
+        })
+    }
+
+    /// Returns the id of `name`, or an error if it is already recorded with a
+    /// different unit.
+    async fn resolve(
+        &mut self,
+        conn: &mut PgConnection,
+        name: &str,
+        unit: &str,
+        report: &mut IamcImportReport,
+    ) -> Result<Result<i32, String>, ApiError> {
+        if let Some((id, existing)) = self.variables.get(&name.to_lowercase()) {
+            if existing != unit {
+                return Ok(Err(format!(
+                    "Unit '{}' does not match the unit '{}' of variable '{}'",
+                    unit, existing, name
+                )));
+            }
+            return Ok(Ok(*id));
+        }
+
+        let unit_id = match self.units.get(unit) {
+            Some(id) => *id,
+            None => {
+                let id = sqlx::query_scalar!("INSERT INTO pbtar.units (name) VALUES ($1) RETURNING id", unit)
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(ApiError::DbError)?;
+                self.units.insert(unit.to_string(), id);
+                report.created_units.push(unit.to_string());
+                id
+            }
+        };
+
+        let id = sqlx::query_scalar!(
+            "INSERT INTO pbtar.variables (name, unit_id) VALUES ($1, $2) RETURNING id",
+            name,
+            unit_id
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(ApiError::DbError)?;
+
+        self.variables.insert(name.to_lowercase(), (id, unit.to_string()));
+        report.created_variables.push(name.to_string());
+
+        Ok(Ok(id))
+    }
+}
+
+/// Loads IAMC wide-format pathway data (`Model`, `Scenario`, `Region`,
+/// `Variable`, `Unit` and one column per year) from CSV or XLSX into the
+/// time-series store. Values replace any already stored for the same
+/// scenario, variable, region and year; rows are loaded without a sector.
+///
+/// Rows naming an unknown scenario, an unknown region (unless
+/// `create_regions`), a unit that conflicts with their variable, or a name
+/// too long to store are rejected and reported. Everything runs in one
+/// transaction that is rolled back when `dry_run`.
+pub async fn import_iamc(
+    db: &PgPool,
+    data: &[u8],
+    dry_run: bool,
+    create_regions: bool,
+) -> Result<IamcImportReport, ApiError> {
+    let table = read_table(data)?;
+    let (headers, rows) = table
+        .split_first()
+        .ok_or_else(|| ApiError::BadRequestError("The file is empty".into()))?;
+    let columns = Columns::from_headers(headers)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    let mut regions = NameIndex::load(&mut tx, "regions", "region").await?;
+    let mut variables = Variables::load(&mut tx).await?;
+    let mut scenarios: HashMap<(String, String), Option<i32>> = HashMap::new();
+
+    let mut report = IamcImportReport::new(dry_run);
+
+    for (index, row) in rows.iter().enumerate() {
+        if row.iter().all(String::is_empty) {
+            continue;
+        }
+
+        let cell = |column: usize| row.get(column).map(String::as_str).unwrap_or_default();
+        let (model, scenario, region, variable, unit) = (
+            cell(columns.model),
+            cell(columns.scenario),
+            cell(columns.region),
+            cell(columns.variable),
+            cell(columns.unit),
+        );
+
+        let mut errors = Vec::new();
+        for (name, value) in [
+            ("Model", model),
+            ("Scenario", scenario),
+            ("Region", region),
+            ("Variable", variable),
+            ("Unit", unit),
+        ] {
+            if value.is_empty() {
+                errors.push(format!("{} must not be empty", name));
+            }
+        }
+        for (name, value, max) in [
+            ("Region", region, REGION_MAX_LENGTH),
+            ("Variable", variable, VARIABLE_MAX_LENGTH),
+            ("Unit", unit, UNIT_MAX_LENGTH),
+        ] {
+            errors.extend(length_error(name, value, max));
+        }
+
+        let mut values: Vec<(i32, f64)> = Vec::new();
+        for &(column, year) in &columns.years {
+            let value = cell(column);
+            if value.is_empty() {
+                continue;
+            }
+            match value.parse::<f64>() {
+                Ok(value) if value.is_finite() => values.push((year, value)),
+                _ => errors.push(format!("Invalid value '{}' for {}", value, year)),
+            }
+        }
+
+        let scenario_id = if scenario.is_empty() {
+            None
+        } else {
+            let id = find_scenario(&mut tx, &mut scenarios, model, scenario).await?;
+            if id.is_none() {
+                let pair = format!("{} / {}", model, scenario);
+                errors.push(format!("Unknown scenario '{}' from model '{}'", scenario, model));
+                if !report.unknown_scenarios.contains(&pair) {
+                    report.unknown_scenarios.push(pair);
+                }
+            }
+            id
+        };
+
+        let region_id = match regions.ids.get(&region.to_lowercase()) {
+            _ if region.is_empty() || region.chars().count() > REGION_MAX_LENGTH => None,
+            Some(id) => Some(*id),
+            None if create_regions => {
+                // Another request may have created the region since the
+                // index was loaded; use that one.
+                let created = sqlx::query_scalar!(
+                    "INSERT INTO pbtar.regions (name) VALUES ($1) ON CONFLICT (name) DO NOTHING RETURNING id",
+                    region
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(ApiError::DbError)?;
+                let id = match created {
+                    Some(id) => {
+                        report.created_regions.push(region.to_string());
+                        id
+                    }
+                    None => sqlx::query_scalar!("SELECT id FROM pbtar.regions WHERE name = $1", region)
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(ApiError::DbError)?,
+                };
+                regions.ids.insert(region.to_lowercase(), id);
+                Some(id)
+            }
+            None => {
+                errors.push(format!("Unknown region '{}'", region));
+                if !report.unknown_regions.iter().any(|r| r.eq_ignore_ascii_case(region)) {
+                    report.unknown_regions.push(region.to_string());
+                }
+                None
+            }
+        };
+
+        // Only create variables for rows that are otherwise valid.
+        let variable_id = if errors.is_empty() {
+            match variables.resolve(&mut tx, variable, unit, &mut report).await? {
+                Ok(id) => Some(id),
+                Err(error) => {
+                    errors.push(error);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let (Some(scenario_id), Some(region_id), Some(variable_id), true) =
+            (scenario_id, region_id, variable_id, errors.is_empty())
+        else {
+            report.reject(IamcRowError {
+                line: index as u64 + 2,
+                model: model.to_string(),
+                scenario: scenario.to_string(),
+                region: region.to_string(),
+                variable: variable.to_string(),
+                errors,
+            });
+            continue;
+        };
+
+        let (years, values): (Vec<i32>, Vec<f64>) = values.into_iter().unzip();
+        sqlx::query!(
+            r#"
+            INSERT INTO pbtar.timeseries (scenario_id, variable_id, region_id, year, value)
+            SELECT $1, $2, $3, UNNEST($4::int[]), UNNEST($5::float8[])
+            ON CONFLICT (scenario_id, variable_id, region_id, year) WHERE sector_id IS NULL
+            DO UPDATE SET value = EXCLUDED.value
+            "#,
+            scenario_id,
+            variable_id,
+            region_id,
+            &years,
+            &values
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::DbError)?;
+
+        report.rows_imported += 1;
+        report.values_written += years.len();
+        if !report.scenarios.iter().any(|s| s.scenario_id == scenario_id) {
+            report.scenarios.push(IamcScenarioMatch {
+                model: model.to_string(),
+                scenario: scenario.to_string(),
+                scenario_id,
+            });
+        }
+    }
+
+    if dry_run {
+        tx.rollback().await.map_err(ApiError::DbError)?;
+    } else {
+        tx.commit().await.map_err(ApiError::DbError)?;
+    }
+
+    Ok(report)
+}
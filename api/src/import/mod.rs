@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use sqlx::types::Json;
+use sqlx::{Acquire, PgConnection, PgPool};
+
+use crate::auth::Claims;
+use crate::db::scenarios;
+use crate::errors::ApiError;
+use crate::export::unescape_formula;
+use crate::models::{
+    CreateScenarioRequest, ImportReport, ImportRowResult, ImportStatus, RevisionAction, ScenarioSnapshot,
+};
+
+mod iamc;
+
+pub use iamc::import_iamc;
+
+/// Separator between several names within one CSV cell, e.g. `Power; Steel`.
+const LIST_SEPARATOR: char = ';';
+
+/// Case-insensitive name to id lookups for one taxonomy table.
+struct NameIndex {
+    label: &'static str,
+    ids: HashMap<String, i32>,
+}
+
+impl NameIndex {
+    async fn load(conn: &mut PgConnection, table: &str, label: &'static str) -> Result<Self, ApiError> {
+        let rows: Vec<(i32, String)> = sqlx::query_as(&format!("SELECT id, name FROM pbtar.{}", table))
+            .fetch_all(conn)
+            .await
+            .map_err(ApiError::DbError)?;
+
+        Ok(Self {
+            label,
+            ids: rows.into_iter().map(|(id, name)| (name.to_lowercase(), id)).collect(),
+        })
+    }
+
+    fn resolve(&self, name: &str, errors: &mut Vec<String>) -> Option<i32> {
+        let id = self.ids.get(&name.trim().to_lowercase()).copied();
+        if id.is_none() {
+            errors.push(format!("Unknown {} '{}'", self.label, name.trim()));
+        }
+        id
+    }
+
+    fn resolve_list(&self, names: &str, errors: &mut Vec<String>) -> Vec<i32> {
+        names
+            .split(LIST_SEPARATOR)
+            .filter(|name| !name.trim().is_empty())
+            .filter_map(|name| self.resolve(name, errors))
+            .collect()
+    }
+}
+
+/// Column positions of a scenario CSV, located by (case-insensitive) header.
+struct Columns {
+    title: usize,
+    type_name: usize,
+    model: Option<usize>,
+    temperature_target: Option<usize>,
+    description: Option<usize>,
+    publisher: Option<usize>,
+    regions: Option<usize>,
+    sectors: Option<usize>,
+    stakeholders: Option<usize>,
+    published_date: Option<usize>,
+    target_year: Option<usize>,
+}
+
+impl Columns {
+    fn from_headers(headers: &csv::StringRecord) -> Result<Self, ApiError> {
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        };
+        let require = |names: &[&str]| {
+            find(names).ok_or_else(|| {
+                ApiError::BadRequestError(format!("Missing required column '{}'", names[0]))
+            })
+        };
+
+        Ok(Self {
+            title: require(&["title"])?,
+            type_name: require(&["type", "type_name"])?,
+            model: find(&["model"]),
+            temperature_target: find(&["temperature_target", "temperature target"]),
+            description: find(&["description"]),
+            publisher: find(&["publisher"]),
+            regions: find(&["regions", "region"]),
+            sectors: find(&["sectors", "sector"]),
+            stakeholders: find(&["stakeholders", "stakeholder"]),
+            published_date: find(&["published_date", "published date"]),
+            target_year: find(&["target_year", "target year"]),
+        })
+    }
+
+    /// The row to write over `existing`: the columns the file has, with
+    /// every field the file has no column for kept from `existing`. An empty
+    /// cell in a column the file has clears the field.
+    fn merge(&self, existing: ScenarioSnapshot, row: &CreateScenarioRequest) -> CreateScenarioRequest {
+        fn pick<T: Clone>(column: Option<usize>, row: &T, existing: T) -> T {
+            match column {
+                Some(_) => row.clone(),
+                None => existing,
+            }
+        }
+
+        CreateScenarioRequest {
+            title: row.title.clone(),
+            type_name: row.type_name.clone(),
+            model: pick(self.model, &row.model, existing.model),
+            temperature_target: pick(self.temperature_target, &row.temperature_target, existing.temperature_target),
+            description: pick(self.description, &row.description, existing.description),
+            publisher_id: pick(self.publisher, &row.publisher_id, existing.publisher_id),
+            published_date: pick(self.published_date, &row.published_date, existing.published_date),
+            target_year: pick(self.target_year, &row.target_year, existing.target_year),
+            region_ids: pick(self.regions, &row.region_ids, existing.region_ids),
+            stakeholder_ids: pick(self.stakeholders, &row.stakeholder_ids, existing.stakeholder_ids),
+            sector_ids: pick(self.sectors, &row.sector_ids, existing.sector_ids),
+            relations: None,
+        }
+    }
+}
+
+struct Indexes {
+    publishers: NameIndex,
+    regions: NameIndex,
+    sectors: NameIndex,
+    stakeholders: NameIndex,
+}
+
+/// Turns one CSV record into a scenario payload, or the reasons it cannot be.
+fn parse_row(
+    record: &csv::StringRecord,
+    columns: &Columns,
+    indexes: &Indexes,
+) -> Result<CreateScenarioRequest, Vec<String>> {
+    // Cells escaped against formulas by the CSV export are read back as is.
+    let cell = |index: Option<usize>| {
+        index
+            .and_then(|i| record.get(i))
+            .map(|value| unescape_formula(value.trim()).trim())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut errors = Vec::new();
+
+    let title = cell(Some(columns.title)).unwrap_or_default().to_string();
+    if title.is_empty() {
+        errors.push("title must not be empty".to_string());
+    }
+    let type_name = cell(Some(columns.type_name)).unwrap_or_default().to_string();
+    if type_name.is_empty() {
+        errors.push("type must not be empty".to_string());
+    }
+    let model = cell(columns.model);
+    let temperature_target = cell(columns.temperature_target);
+    for (field, value, max) in [
+        ("title", Some(title.as_str()), scenarios::TITLE_MAX_LENGTH),
+        ("type", Some(type_name.as_str()), scenarios::TYPE_MAX_LENGTH),
+        ("model", model, scenarios::MODEL_MAX_LENGTH),
+        ("temperature_target", temperature_target, scenarios::TEMPERATURE_TARGET_MAX_LENGTH),
+    ] {
+        errors.extend(value.and_then(|value| scenarios::length_error(field, value, max)));
+    }
+
+    let publisher_id = cell(columns.publisher).and_then(|name| indexes.publishers.resolve(name, &mut errors));
+    let region_ids = cell(columns.regions)
+        .map(|names| indexes.regions.resolve_list(names, &mut errors))
+        .unwrap_or_default();
+    let sector_ids = cell(columns.sectors)
+        .map(|names| indexes.sectors.resolve_list(names, &mut errors))
+        .unwrap_or_default();
+    let stakeholder_ids = cell(columns.stakeholders)
+        .map(|names| indexes.stakeholders.resolve_list(names, &mut errors))
+        .unwrap_or_default();
+
+    let published_date = cell(columns.published_date).and_then(|value| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| errors.push(format!("Invalid published_date '{}', expected YYYY-MM-DD", value)))
+            .ok()
+    });
+    let target_year = cell(columns.target_year).and_then(|value| {
+        value
+            .parse::<i32>()
+            .map_err(|_| errors.push(format!("Invalid target_year '{}'", value)))
+            .ok()
+    });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(CreateScenarioRequest {
+        title,
+        type_name,
+        model: model.map(str::to_string),
+        temperature_target: temperature_target.map(str::to_string),
+        description: cell(columns.description).map(str::to_string),
+        publisher_id,
+        published_date,
+        target_year,
+        region_ids,
+        stakeholder_ids,
+        sector_ids,
+        relations: None,
+    })
+}
+
+/// Creates the scenario of one row, or updates the columns the file has of
+/// an existing one, returning what was done.
+async fn write_row(
+    conn: &mut PgConnection,
+    payload: &CreateScenarioRequest,
+    columns: &Columns,
+    editor: Option<&Claims>,
+) -> Result<(ImportStatus, i32), ApiError> {
+    let existing = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM pbtar.scenarios
+        WHERE lower(title) = lower($1) AND publisher_id IS NOT DISTINCT FROM $2
+        ORDER BY id
+        LIMIT 1
+        "#,
+        payload.title,
+        payload.publisher_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    match existing {
+        Some(id) => {
+            let existing = sqlx::query_scalar!(
+                r#"SELECT pbtar.scenario_snapshot($1) as "snapshot!: Json<ScenarioSnapshot>""#,
+                id
+            )
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(ApiError::DbError)?
+            .0;
+
+            scenarios::replace_scenario(&mut *conn, id, &columns.merge(existing, payload)).await?;
+            scenarios::record_revision(&mut *conn, id, RevisionAction::Update, editor).await?;
+            Ok((ImportStatus::Updated, id))
+        }
+        None => {
+            let id = scenarios::insert_scenario(&mut *conn, payload).await?;
+            scenarios::record_revision(&mut *conn, id, RevisionAction::Create, editor).await?;
+            Ok((ImportStatus::Created, id))
+        }
+    }
+}
+
+/// Imports scenarios from CSV, matching existing scenarios by title and
+/// publisher. Rows that fail validation are reported and skipped; the rest
+/// are written in a single transaction that is rolled back when `dry_run`.
+/// Each row is written under its own savepoint, so that a row the database
+/// rejects is reported like the others instead of aborting the import.
+/// Each write is recorded as a revision by `editor`.
+pub async fn import_scenarios_csv(
+    db: &PgPool,
+    data: &[u8],
+    dry_run: bool,
+    editor: Option<&Claims>,
+) -> Result<ImportReport, ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| ApiError::BadRequestError(format!("Invalid CSV header: {}", e)))?
+        .clone();
+    let columns = Columns::from_headers(&headers)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    let indexes = Indexes {
+        publishers: NameIndex::load(&mut tx, "publishers", "publisher").await?,
+        regions: NameIndex::load(&mut tx, "regions", "region").await?,
+        sectors: NameIndex::load(&mut tx, "sectors", "sector").await?,
+        stakeholders: NameIndex::load(&mut tx, "stakeholders", "stakeholder").await?,
+    };
+
+    let mut report = ImportReport::new(dry_run);
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.push(ImportRowResult {
+                    line: e.position().map(|p| p.line()).unwrap_or_default(),
+                    title: None,
+                    status: ImportStatus::Rejected,
+                    scenario_id: None,
+                    errors: vec![e.to_string()],
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+
+        let payload = match parse_row(&record, &columns, &indexes) {
+            Ok(payload) => payload,
+            Err(errors) => {
+                report.push(ImportRowResult {
+                    line,
+                    title: record.get(columns.title).map(|t| t.trim().to_string()),
+                    status: ImportStatus::Rejected,
+                    scenario_id: None,
+                    errors,
+                });
+                continue;
+            }
+        };
+
+        let mut savepoint = tx.begin().await.map_err(ApiError::DbError)?;
+        let (status, scenario_id) = match write_row(&mut savepoint, &payload, &columns, editor).await {
+            Ok(written) => {
+                savepoint.commit().await.map_err(ApiError::DbError)?;
+                written
+            }
+            Err(e) => {
+                savepoint.rollback().await.map_err(ApiError::DbError)?;
+                report.push(ImportRowResult {
+                    line,
+                    title: Some(payload.title),
+                    status: ImportStatus::Rejected,
+                    scenario_id: None,
+                    errors: vec![e.to_string()],
+                });
+                continue;
+            }
+        };
+
+        report.push(ImportRowResult {
+            line,
+            title: Some(payload.title),
+            status,
+            // Ids of rows created during a dry run are rolled back with it.
+            scenario_id: (!dry_run || status == ImportStatus::Updated).then_some(scenario_id),
+            errors: Vec::new(),
+        });
+    }
+
+    if dry_run {
+        tx.rollback().await.map_err(ApiError::DbError)?;
+    } else {
+        tx.commit().await.map_err(ApiError::DbError)?;
+    }
+
+    Ok(report)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing() -> ScenarioSnapshot {
+        ScenarioSnapshot {
+            title: "Net Zero 2050".into(),
+            type_name: "normative".into(),
+            model: Some("REMIND-MAgPIE 3.2".into()),
+            temperature_target: Some("1.5°C".into()),
+            description: Some("Reaches net zero by 2050".into()),
+            publisher_id: Some(1),
+            published_date: NaiveDate::from_ymd_opt(2023, 5, 1),
+            target_year: Some(2050),
+            region_ids: vec![1, 2],
+            stakeholder_ids: vec![3],
+            sector_ids: vec![4, 5],
+            relations: Vec::new(),
+        }
+    }
+
+    fn row(title: &str, type_name: &str) -> CreateScenarioRequest {
+        CreateScenarioRequest {
+            title: title.into(),
+            type_name: type_name.into(),
+            model: None,
+            temperature_target: None,
+            description: None,
+            publisher_id: None,
+            published_date: None,
+            target_year: None,
+            region_ids: Vec::new(),
+            stakeholder_ids: Vec::new(),
+            sector_ids: Vec::new(),
+            relations: None,
+        }
+    }
+
+    #[test]
+    fn keeps_fields_without_a_column() {
+        let columns = Columns::from_headers(&csv::StringRecord::from(vec!["title", "type", "target_year"])).unwrap();
+        let mut update = row("Net Zero 2050", "exploratory");
+        update.target_year = Some(2060);
+
+        let merged = columns.merge(existing(), &update);
+
+        assert_eq!(merged.type_name, "exploratory");
+        assert_eq!(merged.target_year, Some(2060));
+        assert_eq!(merged.model.as_deref(), Some("REMIND-MAgPIE 3.2"));
+        assert_eq!(merged.temperature_target.as_deref(), Some("1.5°C"));
+        assert_eq!(merged.description.as_deref(), Some("Reaches net zero by 2050"));
+        assert_eq!(merged.publisher_id, Some(1));
+        assert_eq!(merged.published_date, NaiveDate::from_ymd_opt(2023, 5, 1));
+        assert_eq!(merged.region_ids, vec![1, 2]);
+        assert_eq!(merged.stakeholder_ids, vec![3]);
+        assert_eq!(merged.sector_ids, vec![4, 5]);
+        assert!(merged.relations.is_none());
+    }
+
+    #[test]
+    fn clears_fields_with_an_empty_cell() {
+        let columns = Columns::from_headers(&csv::StringRecord::from(vec!["title", "type", "model", "regions"])).unwrap();
+
+        let merged = columns.merge(existing(), &row("Net Zero 2050", "normative"));
+
+        assert_eq!(merged.model, None);
+        assert!(merged.region_ids.is_empty());
+        assert_eq!(merged.sector_ids, vec![4, 5]);
+    }
+}
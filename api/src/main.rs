@@ -1,28 +1,31 @@
-use actix_cors::Cors;
 use actix_web::{
-    http, middleware, web, App, HttpServer,
+    middleware, web, App, HttpServer,
 };
 use dotenv::dotenv;
-use log::{info, error};
-use sqlx::postgres::PgPoolOptions;
-use std::{env, time::Duration};
+use log::{info, error, warn};
+use std::io::Write;
 
+mod auth;
+mod cli;
+mod import;
+mod metrics;
 mod models;
 mod routes;
 mod config;
+mod cors;
 mod db;
 mod errors;
+mod export;
 
-async fn connect_to_db_with_retry(database_url: &str, max_retries: u32) -> Result<sqlx::PgPool, sqlx::Error> {
+use config::{Config, LogFormat};
+
+async fn connect_to_db_with_retry(config: &Config) -> Result<sqlx::PgPool, sqlx::Error> {
+    let max_retries = config.db_connect_retries;
+    let retry_delay = config.db_connect_retry_delay();
     let mut retries = 0;
-    let retry_delay = Duration::from_secs(5);
 
     loop {
-        match PgPoolOptions::new()
-            .max_connections(10)
-            .connect(database_url)
-            .await
-        {
+        match db::create_pool(config).await {
             Ok(pool) => {
                 info!("Successfully connected to database");
                 return Ok(pool);
@@ -41,18 +44,47 @@ async fn connect_to_db_with_retry(database_url: &str, max_retries: u32) -> Resul
     }
 }
 
+/// Sets up `env_logger`, filtered by `RUST_LOG`, writing in `format`.
+fn init_logger(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+
+    builder.init();
+}
+
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     // Initialize environment
     dotenv().ok();
-    env_logger::init();
-    
-    // Load database configuration from environment
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set in environment");
+
+    // Load configuration from defaults, an optional file and the environment
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    init_logger(config.log_format);
+
+    if config.cors_mode == cors::CorsMode::Permissive {
+        warn!("CORS is permissive: any origin may call the API. Use CORS_MODE=strict in production");
+    }
     
     // Create a database connection pool with retry mechanism
-    let db_pool = match connect_to_db_with_retry(&database_url, 10).await {
+    let db_pool = match connect_to_db_with_retry(&config).await {
         Ok(pool) => pool,
         Err(e) => {
             error!("Failed to create database connection pool: {}", e);
@@ -60,25 +92,51 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    // Run an admin command instead of the server when one is given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args, &db_pool).await);
+    }
+
+    if config.run_migrations {
+        if let Err(e) = db::migrate(&db_pool).await {
+            error!("Failed to apply database migrations: {}", e);
+            std::process::exit(1);
+        }
+        info!("Database migrations are up to date");
+    }
+
+    if config.seed_sample_data {
+        match db::seed(&db_pool).await {
+            Ok(true) => info!("Loaded the sample data"),
+            Ok(false) => info!("Database already holds scenarios; sample data not loaded"),
+            Err(e) => error!("Failed to load the sample data: {}", e),
+        }
+    }
+
     // Set up server with database connection pool
-    info!("Starting server at http://0.0.0.0:8080");
+    info!("Starting server at http://{}", config.bind_address);
+
+    let bind_address = config.bind_address.clone();
+    let request_timeout = config.request_timeout();
+    let shutdown_timeout = config.shutdown_timeout;
+    let metrics = web::Data::new(metrics::Metrics::new());
     
     HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin("http://localhost:3000")
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-            .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-            .allowed_header(http::header::CONTENT_TYPE)
-            .supports_credentials()
-            .max_age(3600);
+        let cors = cors::cors(&config);
             
         App::new()
             .wrap(middleware::Logger::default())
             .wrap(cors)
+            .wrap(middleware::from_fn(metrics::track))
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .app_data(metrics.clone())
             .configure(routes::config)
     })
-    .bind(("0.0.0.0", 8080))?
+    .client_request_timeout(request_timeout)
+    .shutdown_timeout(shutdown_timeout)
+    .bind(bind_address)?
     .run()
     .await
 }
@@ -0,0 +1,164 @@
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::errors::ApiError;
+use crate::models::ScenarioFilters;
+
+/// Metrics exposed in the Prometheus text format on `/metrics`. Shared by
+/// every worker through `web::Data`.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    scenarios: IntGauge,
+    scenario_searches: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("pbtar".into()), None)
+            .expect("the metrics prefix is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, by route and status code"),
+            &["method", "route", "status"],
+        )
+        .expect("metric options are valid");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time taken to answer HTTP requests, by route"),
+            &["method", "route"],
+        )
+        .expect("metric options are valid");
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database connections held by the pool, by state"),
+            &["state"],
+        )
+        .expect("metric options are valid");
+        let pool_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Most connections the database pool may open",
+        )
+        .expect("metric options are valid");
+        let scenarios = IntGauge::new("scenarios", "Scenarios in the database")
+            .expect("metric options are valid");
+        let scenario_searches = IntCounterVec::new(
+            Opts::new(
+                "scenario_searches_total",
+                "Scenario list requests, by filter used; a request using several filters counts for each",
+            ),
+            &["filter"],
+        )
+        .expect("metric options are valid");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(pool_connections.clone()),
+            Box::new(pool_max_connections.clone()),
+            Box::new(scenarios.clone()),
+            Box::new(scenario_searches.clone()),
+        ] {
+            registry.register(collector).expect("metric names are unique");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            pool_connections,
+            pool_max_connections,
+            scenarios,
+            scenario_searches,
+        }
+    }
+
+    /// Counts a scenario search once for each kind of filter it uses, or as
+    /// `none` without filters.
+    pub fn record_search(&self, filters: &ScenarioFilters) {
+        let used = filters.filter_types();
+        if used.is_empty() {
+            self.scenario_searches.with_label_values(&["none"]).inc();
+        }
+        for filter in used {
+            self.scenario_searches.with_label_values(&[filter]).inc();
+        }
+    }
+
+    /// Refreshes the gauges read from the database and encodes every metric.
+    /// The scenario count keeps its last value when the database fails.
+    pub async fn render(&self, db: &PgPool) -> Result<String, ApiError> {
+        let size = i64::from(db.size());
+        let idle = db.num_idle() as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections.with_label_values(&["in_use"]).set((size - idle).max(0));
+        self.pool_max_connections.set(i64::from(db.options().get_max_connections()));
+
+        // A database outage must not take the other metrics down with it, so
+        // the gauge keeps its last value instead.
+        match sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM pbtar.scenarios"#)
+            .fetch_one(db)
+            .await
+        {
+            Ok(scenarios) => self.scenarios.set(scenarios),
+            Err(e) => log::error!("Failed to count scenarios for metrics: {}", e),
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| ApiError::InternalError(format!("Failed to encode metrics: {}", e)))?;
+
+        String::from_utf8(buffer)
+            .map_err(|e| ApiError::InternalError(format!("Failed to encode metrics: {}", e)))
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware counting every request and timing it. Requests are labelled
+/// with the route pattern (`/api/scenarios/{id}`) rather than the path, so
+/// that ids do not create new series; requests matching no route are
+/// labelled `unmatched`.
+pub async fn track(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let res = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        let (route, status) = match &res {
+            Ok(res) => (res.request().match_pattern(), res.status()),
+            Err(e) => (None, e.as_response_error().status_code()),
+        };
+        let route = route.unwrap_or_else(|| "unmatched".to_string());
+
+        metrics
+            .http_requests
+            .with_label_values(&[&method, &route, status.as_str()])
+            .inc();
+        metrics
+            .http_request_duration
+            .with_label_values(&[&method, &route])
+            .observe(started.elapsed().as_secs_f64());
+    }
+
+    res
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ApiError;
+
+use super::query::{deserialize_id_list, parse_query};
+use super::{Region, ScenarioDetail, Sector, Stakeholder};
+
+/// At most this many scenarios can be compared at once.
+pub const MAX_COMPARED_SCENARIOS: usize = 10;
+
+/// Query parameters for `GET /api/scenarios/compare`.
+#[derive(Debug, Deserialize)]
+pub struct CompareParams {
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub ids: Vec<i32>,
+}
+
+impl CompareParams {
+    /// Parses the query, requiring between two and `MAX_COMPARED_SCENARIOS`
+    /// distinct ids. Their order is kept.
+    pub fn from_query(query: &str) -> Result<Self, ApiError> {
+        let mut params: Self = parse_query(query)?;
+
+        let mut seen = Vec::with_capacity(params.ids.len());
+        params.ids.retain(|id| {
+            let first = !seen.contains(id);
+            seen.push(*id);
+            first
+        });
+
+        if params.ids.len() < 2 || params.ids.len() > MAX_COMPARED_SCENARIOS {
+            return Err(ApiError::BadRequestError(format!(
+                "ids must name between 2 and {} scenarios",
+                MAX_COMPARED_SCENARIOS
+            )));
+        }
+
+        Ok(params)
+    }
+}
+
+/// One `ScenarioDetail` field across the compared scenarios.
+#[derive(Debug, Serialize)]
+pub struct FieldComparison {
+    pub field: String,
+    /// The field's value for each scenario, in request order.
+    pub values: Vec<serde_json::Value>,
+    /// Whether every scenario has the same value.
+    pub same: bool,
+}
+
+/// How the compared scenarios overlap in one of their taxonomies.
+#[derive(Debug, Serialize)]
+pub struct SetComparison<T> {
+    /// Entries shared by every scenario.
+    pub common: Vec<T>,
+    /// For each scenario, in request order, the entries no other scenario has.
+    pub unique: Vec<Vec<T>>,
+}
+
+impl<T: Clone> SetComparison<T> {
+    pub fn new(sets: &[&[T]], id: impl Fn(&T) -> i32) -> Self {
+        let in_set = |set: &[T], entry: &T| set.iter().any(|other| id(other) == id(entry));
+
+        let common = sets
+            .first()
+            .map(|first| {
+                first
+                    .iter()
+                    .filter(|entry| sets.iter().all(|set| in_set(set, entry)))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let unique = sets
+            .iter()
+            .enumerate()
+            .map(|(index, set)| {
+                set.iter()
+                    .filter(|entry| {
+                        sets.iter()
+                            .enumerate()
+                            .all(|(other, other_set)| other == index || !in_set(other_set, entry))
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+
+        Self { common, unique }
+    }
+}
+
+/// Values of every compared scenario for one year.
+#[derive(Debug, Serialize)]
+pub struct AlignedValue {
+    pub year: i32,
+    /// One value per scenario, in request order; `None` where a scenario has
+    /// no value for the year.
+    pub values: Vec<Option<f64>>,
+}
+
+/// A series (variable, region and sector) that every compared scenario has
+/// data for, aligned by year.
+#[derive(Debug, Serialize)]
+pub struct AlignedTimeseries {
+    pub variable_id: i32,
+    pub variable: String,
+    pub unit: String,
+    pub region_id: i32,
+    pub region: String,
+    pub sector_id: Option<i32>,
+    pub sector: Option<String>,
+    pub values: Vec<AlignedValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScenarioComparison {
+    pub scenarios: Vec<ScenarioDetail>,
+    pub fields: Vec<FieldComparison>,
+    pub regions: SetComparison<Region>,
+    pub sectors: SetComparison<Sector>,
+    pub stakeholders: SetComparison<Stakeholder>,
+    pub timeseries: Vec<AlignedTimeseries>,
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::FromRow;
+
+/// Representation requested for a list endpoint, either through a `format`
+/// query parameter or the `Accept` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub const CSV_MIME: &'static str = "text/csv";
+    pub const XLSX_MIME: &'static str =
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+    /// Picks a spreadsheet format named in an `Accept` header, falling back
+    /// to JSON.
+    pub fn from_accept(accept: &str) -> Self {
+        if accept.contains(Self::XLSX_MIME) {
+            Self::Xlsx
+        } else if accept.contains(Self::CSV_MIME) {
+            Self::Csv
+        } else {
+            Self::Json
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => Self::XLSX_MIME,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// One scenario flattened into a spreadsheet row, with its regions, sectors
+/// and stakeholders joined into `; `-separated names.
+#[derive(Debug, FromRow)]
+pub struct ScenarioExportRow {
+    pub id: i32,
+    pub title: String,
+    pub type_name: String,
+    pub temperature_target: Option<String>,
+    pub temperature_lower: Option<f64>,
+    pub temperature_upper: Option<f64>,
+    pub temperature_probability: Option<f64>,
+    pub temperature_overshoot: Option<bool>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub published_date: Option<NaiveDate>,
+    pub target_year: Option<i32>,
+    pub regions: String,
+    pub sectors: String,
+    pub stakeholders: String,
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ImportParams {
+    /// Validate and report without committing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Created,
+    Updated,
+    Rejected,
+}
+
+/// Outcome of one input row.
+#[derive(Debug, Serialize)]
+pub struct ImportRowResult {
+    /// Line number in the uploaded file, counting the header as line 1.
+    pub line: u64,
+    pub title: Option<String>,
+    pub status: ImportStatus,
+    pub scenario_id: Option<i32>,
+    /// Reasons the row was rejected; empty otherwise.
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            created: 0,
+            updated: 0,
+            rejected: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: ImportRowResult) {
+        match row.status {
+            ImportStatus::Created => self.created += 1,
+            ImportStatus::Updated => self.updated += 1,
+            ImportStatus::Rejected => self.rejected += 1,
+        }
+        self.rows.push(row);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IamcImportParams {
+    /// Validate and report without committing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Create regions the file names but the database does not know, rather
+    /// than rejecting their rows. Admins only.
+    #[serde(default)]
+    pub create_regions: bool,
+}
+
+/// An IAMC row that could not be loaded.
+#[derive(Debug, Serialize)]
+pub struct IamcRowError {
+    /// Line number in the uploaded file, counting the header as line 1.
+    pub line: u64,
+    pub model: String,
+    pub scenario: String,
+    pub region: String,
+    pub variable: String,
+    pub errors: Vec<String>,
+}
+
+/// A `Model`/`Scenario` pair from the file and the scenario it was loaded into.
+#[derive(Debug, Serialize)]
+pub struct IamcScenarioMatch {
+    pub model: String,
+    pub scenario: String,
+    pub scenario_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IamcImportReport {
+    pub dry_run: bool,
+    pub rows_imported: usize,
+    pub rows_rejected: usize,
+    pub values_written: usize,
+    pub scenarios: Vec<IamcScenarioMatch>,
+    /// `Model / Scenario` pairs with no matching scenario.
+    pub unknown_scenarios: Vec<String>,
+    /// Regions that were not found and not created.
+    pub unknown_regions: Vec<String>,
+    pub created_regions: Vec<String>,
+    pub created_variables: Vec<String>,
+    pub created_units: Vec<String>,
+    pub rejected: Vec<IamcRowError>,
+}
+
+impl IamcImportReport {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            rows_imported: 0,
+            rows_rejected: 0,
+            values_written: 0,
+            scenarios: Vec::new(),
+            unknown_scenarios: Vec::new(),
+            unknown_regions: Vec::new(),
+            created_regions: Vec::new(),
+            created_variables: Vec::new(),
+            created_units: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    pub fn reject(&mut self, row: IamcRowError) {
+        self.rows_rejected += 1;
+        self.rejected.push(row);
+    }
+}
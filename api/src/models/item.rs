@@ -2,12 +2,24 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+/// Where an item stands. New items are `active`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ItemStatus {
+    #[default]
+    Active,
+    InProgress,
+    Done,
+    Archived,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Item {
     pub id: i32,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,
+    pub status: ItemStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -16,21 +28,24 @@ pub struct Item {
 pub struct CreateItemRequest {
     pub title: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub status: ItemStatus,
 }
 
+/// Payload for `PUT /api/items/{id}`. Omitted fields keep their value.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateItemRequest {
     pub title: Option<String>,
-    pub description: Option<String>,
-    pub status: Option<String>,
+    /// `null` clears the description; omitting it keeps the current one.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub description: Option<Option<String>>,
+    pub status: Option<ItemStatus>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ItemResponse {
-    pub id: i32,
-    pub title: String,
-    pub description: Option<String>,
-    pub status: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
\ No newline at end of file
+/// Query parameters for `GET /api/items`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ItemFilters {
+    pub status: Option<ItemStatus>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
@@ -1,5 +1,26 @@
+mod compare;
+mod export;
+mod import;
 mod item;
+mod pagination;
+mod query;
+mod relation;
+mod revision;
 mod scenario;
+mod taxonomy;
+mod temperature;
+mod timeseries;
+mod user;
 
+pub use compare::*;
+pub use export::*;
+pub use import::*;
 pub use item::*;
-pub use scenario::*;
\ No newline at end of file
+pub use pagination::*;
+pub use relation::*;
+pub use revision::*;
+pub use scenario::*;
+pub use taxonomy::*;
+pub use temperature::*;
+pub use timeseries::*;
+pub use user::*;
\ No newline at end of file
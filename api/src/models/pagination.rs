@@ -0,0 +1,91 @@
+use serde::Serialize;
+
+use crate::errors::ApiError;
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// A validated page request. `per_page` is capped at `MAX_PER_PAGE` so a
+/// single request can never pull a whole table.
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl Pagination {
+    pub fn new(page: Option<i64>, per_page: Option<i64>) -> Result<Self, ApiError> {
+        let page = page.unwrap_or(1);
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+
+        if page < 1 {
+            return Err(ApiError::BadRequestError("page must be at least 1".into()));
+        }
+        if per_page < 1 {
+            return Err(ApiError::BadRequestError("per_page must be at least 1".into()));
+        }
+
+        let per_page = per_page.min(MAX_PER_PAGE);
+
+        // Keep `offset` from overflowing on absurd page numbers.
+        if (page - 1).checked_mul(per_page).is_none() {
+            return Err(ApiError::BadRequestError(format!("page {} is out of range", page)));
+        }
+
+        Ok(Self { page, per_page })
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+/// Response envelope for paginated list endpoints.
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub total_pages: i64,
+    /// Link to the next page, preserving the other query parameters.
+    pub next: Option<String>,
+    /// Link to the previous page, preserving the other query parameters.
+    pub prev: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    /// Builds the envelope for `items`, deriving the next/prev links from the
+    /// request `path` and `query` string.
+    pub fn new(items: Vec<T>, total: i64, pagination: Pagination, path: &str, query: &str) -> Self {
+        let total_pages = (total + pagination.per_page - 1) / pagination.per_page;
+
+        let next = (pagination.page < total_pages)
+            .then(|| page_link(path, query, pagination.page + 1));
+        let prev = (pagination.page > 1)
+            .then(|| page_link(path, query, (pagination.page - 1).min(total_pages.max(1))));
+
+        Self {
+            items,
+            total,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total_pages,
+            next,
+            prev,
+        }
+    }
+}
+
+fn page_link(path: &str, query: &str, page: i64) -> String {
+    let mut params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    params.retain(|(key, _)| key != "page");
+    params.push(("page".into(), page.to_string()));
+
+    let query = serde_urlencoded::to_string(&params).unwrap_or_default();
+    format!("{}?{}", path, query)
+}
\ No newline at end of file
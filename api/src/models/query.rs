@@ -0,0 +1,61 @@
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer};
+
+use crate::errors::ApiError;
+
+/// Parses a raw query string into `T`, folding repeated keys into a single
+/// comma-separated value first so that `region_id=3&region_id=7` and
+/// `region_id=3,7` are equivalent.
+pub fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, ApiError> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query)
+        .map_err(|e| ApiError::BadRequestError(format!("Invalid query string: {}", e)))?;
+
+    let mut merged: Vec<(String, String)> = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs {
+        match merged.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => {
+                existing.push(',');
+                existing.push_str(&value);
+            }
+            None => merged.push((key, value)),
+        }
+    }
+
+    let query = serde_urlencoded::to_string(&merged)
+        .map_err(|e| ApiError::BadRequestError(format!("Invalid query string: {}", e)))?;
+
+    serde_urlencoded::from_str(&query)
+        .map_err(|e| ApiError::BadRequestError(format!("Invalid filter: {}", e)))
+}
+
+/// Deserializes a comma-separated list of ids.
+pub fn deserialize_id_list<'de, D>(deserializer: D) -> Result<Vec<i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+
+    raw.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .map_err(|_| de::Error::custom(format!("invalid id '{}'", id)))
+        })
+        .collect()
+}
+
+/// Deserializes a comma-separated list of names.
+pub fn deserialize_name_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+
+    Ok(raw
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
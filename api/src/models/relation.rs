@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// How one scenario relates to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum RelationKind {
+    /// A newer edition replacing the related scenario, e.g. WEO 2024 over
+    /// WEO 2023.
+    Supersedes,
+    /// A variant built on the related scenario.
+    DerivedFrom,
+    /// Belongs to the same series as the related scenario.
+    PartOfSeries,
+}
+
+impl RelationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationKind::Supersedes => "supersedes",
+            RelationKind::DerivedFrom => "derived_from",
+            RelationKind::PartOfSeries => "part_of_series",
+        }
+    }
+}
+
+/// Whether a relation is held by the scenario being viewed or points at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationDirection {
+    /// This scenario `relation` the other one (it supersedes it).
+    Outgoing,
+    /// The other scenario `relation` this one (it is superseded by it).
+    Incoming,
+}
+
+/// A relation as written by clients, from the scenario being saved to
+/// `scenario_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioRelationInput {
+    pub relation: RelationKind,
+    pub scenario_id: i32,
+}
+
+/// A relation as shown on `ScenarioDetail`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioRelation {
+    pub relation: RelationKind,
+    pub direction: RelationDirection,
+    pub scenario_id: i32,
+    /// `None` when a past revision refers to a scenario deleted since.
+    pub title: Option<String>,
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ScenarioRelationInput;
+
+/// What a revision did to its scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum RevisionAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// The stored state of a scenario and its associations at one revision, as
+/// built by the `pbtar.scenario_snapshot()` database function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioSnapshot {
+    pub title: String,
+    pub type_name: String,
+    pub model: Option<String>,
+    pub temperature_target: Option<String>,
+    pub description: Option<String>,
+    pub publisher_id: Option<i32>,
+    pub published_date: Option<NaiveDate>,
+    pub target_year: Option<i32>,
+    pub region_ids: Vec<i32>,
+    pub stakeholder_ids: Vec<i32>,
+    pub sector_ids: Vec<i32>,
+    #[serde(default)]
+    pub relations: Vec<ScenarioRelationInput>,
+}
+
+impl ScenarioSnapshot {
+    /// The fields that differ from `previous`. Every field with a value
+    /// counts as changed when there is no previous revision.
+    pub fn changes_since(&self, previous: Option<&ScenarioSnapshot>) -> Vec<FieldChange> {
+        let current = serde_json::to_value(self).unwrap_or_default();
+        let previous = previous
+            .map(|p| serde_json::to_value(p).unwrap_or_default())
+            .unwrap_or_default();
+
+        let Some(fields) = current.as_object() else {
+            return Vec::new();
+        };
+
+        fields
+            .iter()
+            .filter_map(|(field, to)| {
+                let from = previous.get(field).cloned().unwrap_or_default();
+                let empty = to.is_null() || to.as_array().is_some_and(Vec::is_empty);
+                if from == *to || (from.is_null() && empty) {
+                    return None;
+                }
+                Some(FieldChange {
+                    field: field.clone(),
+                    from,
+                    to: to.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+/// One entry of a scenario's history.
+#[derive(Debug, Serialize)]
+pub struct ScenarioRevision {
+    pub revision: i32,
+    pub action: RevisionAction,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Fields changed relative to the previous revision.
+    pub changes: Vec<FieldChange>,
+}
+
+/// Query parameters for `GET /api/scenarios/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct ScenarioQuery {
+    /// Return the scenario as it was at this revision.
+    pub revision: Option<i32>,
+}
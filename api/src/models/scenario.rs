@@ -2,12 +2,21 @@ use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::errors::ApiError;
+use crate::models::{ExportFormat, ScenarioRelation, ScenarioRelationInput, Variable};
+
+use super::query::{deserialize_id_list, parse_query};
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Scenario {
     pub id: i32,
     pub title: String,
     pub type_name: String,
     pub temperature_target: Option<String>,
+    pub temperature_lower: Option<f64>,
+    pub temperature_upper: Option<f64>,
+    pub temperature_probability: Option<f64>,
+    pub temperature_overshoot: Option<bool>,
     pub description: Option<String>,
     pub publisher_id: Option<i32>,
     pub published_date: Option<NaiveDate>,
@@ -16,28 +25,37 @@ pub struct Scenario {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Publisher {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
+    pub website: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Region {
     pub id: i32,
     pub name: String,
     pub parent_id: Option<i32>,
 }
 
+/// A region together with its nested subregions.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct RegionNode {
+    pub id: i32,
+    pub name: String,
+    pub children: Vec<RegionNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stakeholder {
     pub id: i32,
     pub name: String,
     pub type_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sector {
     pub id: i32,
     pub name: String,
@@ -48,7 +66,13 @@ pub struct ScenarioDetail {
     pub id: i32,
     pub title: String,
     pub type_name: String,
+    /// Integrated assessment model that produced the pathway data, if any.
+    pub model: Option<String>,
     pub temperature_target: Option<String>,
+    pub temperature_lower: Option<f64>,
+    pub temperature_upper: Option<f64>,
+    pub temperature_probability: Option<f64>,
+    pub temperature_overshoot: Option<bool>,
     pub description: Option<String>,
     pub published_date: Option<NaiveDate>,
     pub target_year: Option<i32>,
@@ -56,6 +80,11 @@ pub struct ScenarioDetail {
     pub regions: Vec<Region>,
     pub stakeholders: Vec<Stakeholder>,
     pub sectors: Vec<Sector>,
+    /// Editions, derivatives and series members linked to this scenario.
+    pub relations: Vec<ScenarioRelation>,
+    /// Variables with pathway data, available from
+    /// `GET /api/scenarios/{id}/timeseries`.
+    pub variables: Vec<Variable>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -64,20 +93,278 @@ pub struct ScenarioListItem {
     pub title: String,
     pub type_name: String,
     pub temperature_target: Option<String>,
+    pub temperature_lower: Option<f64>,
+    pub temperature_upper: Option<f64>,
+    pub temperature_probability: Option<f64>,
+    pub temperature_overshoot: Option<bool>,
     pub description: Option<String>,
     pub publisher: Option<String>,
     pub published_date: Option<NaiveDate>,
     pub target_year: Option<i32>,
+    /// Full-text search rank; only present when the list is filtered by `q`.
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<f32>,
+    /// HTML-escaped title with the matched terms wrapped in `<mark>` tags.
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_highlight: Option<String>,
+    /// HTML-escaped fragments of the description around the matched terms,
+    /// marked like `title_highlight`.
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_highlight: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Delimiters the database puts around matched terms in highlights, turned
+/// into `<mark>` tags by `ScenarioListItem::mark_highlights`. Control
+/// characters, so that they never clash with stored text.
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+
+impl ScenarioListItem {
+    /// HTML-escapes the highlights as read from the database and marks the
+    /// matched terms, so that clients can render them as markup.
+    pub fn mark_highlights(&mut self) {
+        for text in [&mut self.title_highlight, &mut self.description_highlight]
+            .into_iter()
+            .flatten()
+        {
+            *text = highlight_html(text);
+        }
+    }
+}
+
+fn highlight_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+/// How several ids for one of the junction-table dimensions are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// The scenario is tagged with at least one of the ids.
+    #[default]
+    Any,
+    /// The scenario is tagged with every one of the ids.
+    All,
+}
+
+/// Query parameters of `GET /api/scenarios`.
+///
+/// Every `*_id` dimension accepts several ids, either comma-separated
+/// (`region_id=3,7`) or repeated (`region_id=3&region_id=7`). The matching
+/// `exclude_*` parameter drops scenarios tagged with any of its ids.
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ScenarioFilters {
-    pub publisher_id: Option<i32>,
-    pub region_id: Option<i32>,
-    pub stakeholder_id: Option<i32>,
-    pub sector_id: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub publisher_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub region_id: Vec<i32>,
+    #[serde(default)]
+    pub region_match: MatchMode,
+    /// Also match scenarios tagged with any subregion of `region_id`.
+    #[serde(default)]
+    pub include_subregions: bool,
+    /// Also match scenarios tagged with any region containing `region_id`.
+    #[serde(default)]
+    pub include_parent_regions: bool,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub stakeholder_id: Vec<i32>,
+    #[serde(default)]
+    pub stakeholder_match: MatchMode,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub sector_id: Vec<i32>,
+    #[serde(default)]
+    pub sector_match: MatchMode,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub exclude_publisher_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub exclude_region_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub exclude_stakeholder_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub exclude_sector_id: Vec<i32>,
+    /// Free-text search over title, description and publisher name, using
+    /// web search syntax (`"quoted phrases"`, `or`, `-excluded`).
+    pub q: Option<String>,
     pub type_name: Option<String>,
     pub temperature_target: Option<String>,
+    /// Only scenarios whose lower temperature bound is at least this, in °C.
+    /// Targets without a lower bound, like `well below 2°C`, are excluded.
+    pub temp_min: Option<f64>,
+    /// Only scenarios whose upper temperature bound is at most this, in °C.
+    /// Targets without an upper bound, like `above 3°C`, are excluded.
+    pub temp_max: Option<f64>,
     pub year_from: Option<i32>,
     pub year_to: Option<i32>,
-}
\ No newline at end of file
+    /// Comma-separated sort keys, each optionally prefixed with `-` for
+    /// descending order, e.g. `publisher,-published_date`.
+    pub sort: Option<String>,
+    /// Hide scenarios superseded by a newer edition.
+    #[serde(default)]
+    pub latest_only: bool,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// `csv` or `xlsx` to download every matching scenario instead of a
+    /// page of JSON. Takes precedence over the `Accept` header.
+    pub format: Option<ExportFormat>,
+}
+
+impl ScenarioFilters {
+    /// The search terms, if `q` holds anything besides whitespace.
+    pub fn search_terms(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    /// The kinds of filters narrowing the list, as reported in the metrics.
+    pub fn filter_types(&self) -> Vec<&'static str> {
+        [
+            ("publisher", !self.publisher_id.is_empty()),
+            ("region", !self.region_id.is_empty()),
+            ("stakeholder", !self.stakeholder_id.is_empty()),
+            ("sector", !self.sector_id.is_empty()),
+            (
+                "exclude",
+                !(self.exclude_publisher_id.is_empty()
+                    && self.exclude_region_id.is_empty()
+                    && self.exclude_stakeholder_id.is_empty()
+                    && self.exclude_sector_id.is_empty()),
+            ),
+            ("text", self.search_terms().is_some()),
+            ("type", self.type_name.is_some()),
+            ("temperature_target", self.temperature_target.is_some()),
+            ("temperature_range", self.temp_min.is_some() || self.temp_max.is_some()),
+            ("year", self.year_from.is_some() || self.year_to.is_some()),
+            ("latest_only", self.latest_only),
+        ]
+        .into_iter()
+        .filter_map(|(name, used)| used.then_some(name))
+        .collect()
+    }
+
+    /// Parses a raw query string, accepting repeated keys for id lists.
+    pub fn from_query(query: &str) -> Result<Self, ApiError> {
+        parse_query(query)
+    }
+}
+
+/// Query parameters for `GET /api/scenarios/filters/options`, next to the
+/// `ScenarioFilters` that facet counts are computed for.
+#[derive(Debug, Default, Deserialize)]
+pub struct FilterOptionsParams {
+    /// Count the matching scenarios of every option.
+    #[serde(default)]
+    pub facets: bool,
+}
+
+/// A filter option with the number of scenarios the list would show if it
+/// were selected.
+#[derive(Debug, Serialize)]
+pub struct Facet<T> {
+    #[serde(flatten)]
+    pub option: T,
+    pub count: i64,
+}
+
+/// A free-text filter option, such as a scenario type, with its count.
+#[derive(Debug, Serialize)]
+pub struct FacetValue {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Filter options with counts, as returned in facets mode.
+#[derive(Debug, Serialize)]
+pub struct FilterFacets {
+    pub publishers: Vec<Facet<Publisher>>,
+    pub regions: Vec<Facet<Region>>,
+    pub stakeholders: Vec<Facet<Stakeholder>>,
+    pub sectors: Vec<Facet<Sector>>,
+    pub types: Vec<FacetValue>,
+    pub temperature_targets: Vec<FacetValue>,
+}
+
+/// Payload for `POST /api/scenarios` and `PUT /api/scenarios/{id}`.
+///
+/// Mirrors `ScenarioDetail`, but references the publisher and the taxonomy
+/// entries by id.
+#[derive(Debug, Deserialize)]
+pub struct CreateScenarioRequest {
+    pub title: String,
+    pub type_name: String,
+    /// Integrated assessment model, matched against the `Model` column of
+    /// IAMC pathway imports.
+    #[serde(default)]
+    pub model: Option<String>,
+    pub temperature_target: Option<String>,
+    pub description: Option<String>,
+    pub publisher_id: Option<i32>,
+    pub published_date: Option<NaiveDate>,
+    pub target_year: Option<i32>,
+    #[serde(default)]
+    pub region_ids: Vec<i32>,
+    #[serde(default)]
+    pub stakeholder_ids: Vec<i32>,
+    #[serde(default)]
+    pub sector_ids: Vec<i32>,
+    /// Relations from this scenario to others. When omitted, existing
+    /// relations are kept.
+    #[serde(default)]
+    pub relations: Option<Vec<ScenarioRelationInput>>,
+}
+
+/// Payload for `PATCH /api/scenarios/{id}`. Omitted fields are left unchanged,
+/// `null` clears an optional field, and a supplied id list replaces the
+/// existing associations.
+#[derive(Debug, Deserialize)]
+pub struct UpdateScenarioRequest {
+    pub title: Option<String>,
+    pub type_name: Option<String>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub model: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub temperature_target: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub description: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub publisher_id: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub published_date: Option<Option<NaiveDate>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub target_year: Option<Option<i32>>,
+    pub region_ids: Option<Vec<i32>>,
+    pub stakeholder_ids: Option<Vec<i32>>,
+    pub sector_ids: Option<Vec<i32>>,
+    pub relations: Option<Vec<ScenarioRelationInput>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_highlights_and_marks_matches() {
+        let text = format!(
+            "<img src=x onerror=\"alert('{}net{}')\"> & zero",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        assert_eq!(
+            highlight_html(&text),
+            "&lt;img src=x onerror=&quot;alert(&#39;<mark>net</mark>&#39;)&quot;&gt; &amp; zero"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+/// Body of `POST /api/publishers` and `PUT /api/publishers/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct PublisherRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// An `http://` or `https://` URL.
+    pub website: Option<String>,
+}
+
+/// Body of `POST /api/regions` and `PUT /api/regions/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct RegionRequest {
+    pub name: String,
+    /// The enclosing region, which must not be the region itself or one of
+    /// its subregions.
+    pub parent_id: Option<i32>,
+}
+
+/// Body of `POST /api/stakeholders` and `PUT /api/stakeholders/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct StakeholderRequest {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// Body of `POST /api/sectors` and `PUT /api/sectors/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct SectorRequest {
+    pub name: String,
+}
+
+/// Query parameters for deleting a publisher, region, stakeholder or sector.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteParams {
+    /// Moves scenarios, and pathway data, that refer to the deleted entry to
+    /// this one instead. Without it, deleting an entry in use is refused.
+    pub reassign_to: Option<i32>,
+}
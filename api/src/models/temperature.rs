@@ -0,0 +1,216 @@
+/// A temperature target label such as `1.5°C`, `1.5-2°C`, `well below 2°C`
+/// or `1.5°C (50%) with no or limited overshoot`, broken into its parts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TemperatureTarget {
+    /// Lower bound in °C, `None` for open-ended targets like `below 2°C`.
+    pub lower: Option<f64>,
+    /// Upper bound in °C, `None` for open-ended targets like `above 3°C`.
+    pub upper: Option<f64>,
+    /// Likelihood of staying within the target, as a fraction.
+    pub probability: Option<f64>,
+    /// Whether the pathway overshoots the target before returning to it.
+    /// `Some(false)` for "no overshoot" and "no or limited overshoot".
+    pub overshoot: Option<bool>,
+}
+
+impl TemperatureTarget {
+    /// Parses a free-text label. Only numbers followed by a unit (`°C`, `C`,
+    /// `K`, `degrees`), or ranges ending in one, count as temperatures, and
+    /// a single temperature is open-ended only when a qualifier such as
+    /// `below` or `>` directly precedes it. Parts that cannot be recognised
+    /// are left as `None`, so an unrecognised label yields an empty target.
+    pub fn parse(label: &str) -> Self {
+        let text = label.to_lowercase();
+
+        let mut temperatures = Vec::new();
+        let mut probability = None;
+        for number in numbers(&text) {
+            match number.kind {
+                NumberKind::Percentage => probability = Some(number.value / 100.0),
+                NumberKind::Temperature => temperatures.push(number),
+                NumberKind::Other => {}
+            }
+        }
+
+        let (lower, upper) = match temperatures.as_slice() {
+            [] => (None, None),
+            [single] => {
+                let before = text[..single.start].trim_end();
+                if ["below", "under", "less than", "<", "≤"].iter().any(|w| before.ends_with(w)) {
+                    (None, Some(single.value))
+                } else if ["above", "more than", "at least", ">", "≥"].iter().any(|w| before.ends_with(w)) {
+                    (Some(single.value), None)
+                } else {
+                    (Some(single.value), Some(single.value))
+                }
+            }
+            [a, b, ..] => (Some(a.value.min(b.value)), Some(a.value.max(b.value))),
+        };
+
+        let overshoot = if ["no overshoot", "without overshoot", "no or limited overshoot", "no or low overshoot"]
+            .iter()
+            .any(|phrase| text.contains(phrase))
+        {
+            Some(false)
+        } else if text.contains("overshoot") {
+            Some(true)
+        } else {
+            None
+        };
+
+        Self {
+            lower,
+            upper,
+            probability,
+            overshoot,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberKind {
+    Temperature,
+    Percentage,
+    /// A year, a category such as `C1`, or any other number.
+    Other,
+}
+
+/// A number found in a label, with the byte offset it starts at.
+#[derive(Debug, Clone, Copy)]
+struct Number {
+    start: usize,
+    end: usize,
+    value: f64,
+    kind: NumberKind,
+}
+
+/// Every decimal number in `text`, classified by what follows it: `%` for a
+/// percentage, a temperature unit, or a range separator (`-`, `–`, `to`)
+/// leading to a temperature.
+fn numbers(text: &str) -> Vec<Number> {
+    let mut found = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if !c.is_ascii_digit() {
+            continue;
+        }
+        // A digit right after a letter belongs to a code such as `C1`.
+        let in_code = text[..start].chars().next_back().is_some_and(char::is_alphabetic);
+
+        let mut end = start + c.len_utf8();
+        while let Some(&(i, c)) = chars.peek() {
+            if !(c.is_ascii_digit() || c == '.') {
+                break;
+            }
+            end = i + c.len_utf8();
+            chars.next();
+        }
+
+        let literal = text[start..end].trim_end_matches('.');
+        let Ok(value) = literal.parse::<f64>() else {
+            continue;
+        };
+
+        let rest = text[end..].trim_start();
+        let unit_follows = rest.starts_with('°')
+            || ["c", "k"].iter().any(|unit| {
+                rest.strip_prefix(unit)
+                    .is_some_and(|after| !after.starts_with(char::is_alphabetic))
+            })
+            || rest.starts_with("degree");
+        let kind = if in_code {
+            NumberKind::Other
+        } else if rest.starts_with('%') {
+            NumberKind::Percentage
+        } else if unit_follows {
+            NumberKind::Temperature
+        } else {
+            NumberKind::Other
+        };
+
+        found.push(Number { start, end: start + literal.len(), value, kind });
+    }
+
+    // The first number of a range such as `1.5-2°C` takes the unit of the
+    // second one.
+    for i in (0..found.len().saturating_sub(1)).rev() {
+        let (current, next) = (found[i], found[i + 1]);
+        if current.kind != NumberKind::Other || next.kind != NumberKind::Temperature {
+            continue;
+        }
+        let between = text[current.end..next.start].trim();
+        if ["-", "–", "to"].contains(&between) {
+            found[i].kind = NumberKind::Temperature;
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_single_temperature() {
+        assert_eq!(
+            TemperatureTarget::parse("1.5°C"),
+            TemperatureTarget { lower: Some(1.5), upper: Some(1.5), probability: None, overshoot: None }
+        );
+    }
+
+    #[test]
+    fn parses_a_range() {
+        let target = TemperatureTarget::parse("1.5-2°C");
+        assert_eq!((target.lower, target.upper), (Some(1.5), Some(2.0)));
+    }
+
+    #[test]
+    fn leaves_open_ended_bounds_empty() {
+        let target = TemperatureTarget::parse("well below 2°C");
+        assert_eq!((target.lower, target.upper), (None, Some(2.0)));
+
+        let target = TemperatureTarget::parse("above 3°C");
+        assert_eq!((target.lower, target.upper), (Some(3.0), None));
+    }
+
+    #[test]
+    fn only_reads_qualifiers_directly_before_the_temperature() {
+        assert_eq!(
+            TemperatureTarget::parse("1.5°C (>50%)"),
+            TemperatureTarget { lower: Some(1.5), upper: Some(1.5), probability: Some(0.5), overshoot: None }
+        );
+
+        let target = TemperatureTarget::parse("below 2°C (>66%)");
+        assert_eq!((target.lower, target.upper), (None, Some(2.0)));
+    }
+
+    #[test]
+    fn skips_numbers_without_a_unit() {
+        let target = TemperatureTarget::parse("C1: 1.5°C");
+        assert_eq!((target.lower, target.upper), (Some(1.5), Some(1.5)));
+
+        let target = TemperatureTarget::parse("C3: likely below 2°C by 2100");
+        assert_eq!((target.lower, target.upper), (None, Some(2.0)));
+
+        let target = TemperatureTarget::parse("2 to 3 K");
+        assert_eq!((target.lower, target.upper), (Some(2.0), Some(3.0)));
+    }
+
+    #[test]
+    fn parses_probability_and_overshoot() {
+        assert_eq!(
+            TemperatureTarget::parse("1.5°C (50%) with no or limited overshoot"),
+            TemperatureTarget { lower: Some(1.5), upper: Some(1.5), probability: Some(0.5), overshoot: Some(false) }
+        );
+        assert_eq!(TemperatureTarget::parse("1.5°C with high overshoot").overshoot, Some(true));
+    }
+
+    #[test]
+    fn ignores_unrecognised_labels() {
+        assert_eq!(TemperatureTarget::parse("current policies"), TemperatureTarget::default());
+        assert_eq!(TemperatureTarget::parse("net zero by 2050"), TemperatureTarget::default());
+        assert_eq!(TemperatureTarget::parse(""), TemperatureTarget::default());
+    }
+}
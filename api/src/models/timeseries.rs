@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::errors::ApiError;
+
+use super::query::{deserialize_id_list, deserialize_name_list, parse_query};
+
+/// A quantity reported by scenario pathways, named in the IAMC style
+/// (`Emissions|CO2`, `Capacity|Electricity|Solar`).
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Variable {
+    pub id: i32,
+    pub name: String,
+    pub unit: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TimeseriesValue {
+    pub year: i32,
+    pub value: f64,
+}
+
+/// The yearly values of one variable for one region and, optionally, sector.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Timeseries {
+    pub variable_id: i32,
+    pub variable: String,
+    pub unit: String,
+    pub region_id: i32,
+    pub region: String,
+    pub sector_id: Option<i32>,
+    pub sector: Option<String>,
+    pub values: Vec<TimeseriesValue>,
+}
+
+/// Query parameters for `GET /api/scenarios/{id}/timeseries`. Lists accept
+/// comma-separated or repeated values.
+#[derive(Debug, Default, Deserialize)]
+pub struct TimeseriesFilters {
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub variable_id: Vec<i32>,
+    /// Variable names, where `*` matches any run of characters, e.g.
+    /// `Emissions|*`.
+    #[serde(default, deserialize_with = "deserialize_name_list")]
+    pub variable: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub region_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub sector_id: Vec<i32>,
+    pub year_from: Option<i32>,
+    pub year_to: Option<i32>,
+}
+
+impl TimeseriesFilters {
+    pub fn from_query(query: &str) -> Result<Self, ApiError> {
+        parse_query(query)
+    }
+
+    /// The `variable` patterns translated to SQL `LIKE` patterns.
+    pub fn variable_patterns(&self) -> Vec<String> {
+        like_patterns(&self.variable)
+    }
+}
+
+/// Translates `*` wildcards into SQL `LIKE` patterns, escaping everything
+/// else.
+fn like_patterns(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            pattern
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+                .replace('*', "%")
+        })
+        .collect()
+}
+
+/// Table layouts of the IAMC format: one column per year, or one row per
+/// year with `Year` and `Value` columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IamcLayout {
+    #[default]
+    Wide,
+    Long,
+}
+
+/// Query parameters for `GET /api/scenarios/timeseries/export`.
+#[derive(Debug, Default, Deserialize)]
+pub struct TimeseriesExportParams {
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub scenario_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub variable_id: Vec<i32>,
+    /// Variable names, where `*` matches any run of characters.
+    #[serde(default, deserialize_with = "deserialize_name_list")]
+    pub variable: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub region_id: Vec<i32>,
+    #[serde(default, deserialize_with = "deserialize_id_list")]
+    pub sector_id: Vec<i32>,
+    pub year_from: Option<i32>,
+    pub year_to: Option<i32>,
+    #[serde(default)]
+    pub layout: IamcLayout,
+}
+
+impl TimeseriesExportParams {
+    pub fn from_query(query: &str) -> Result<Self, ApiError> {
+        parse_query(query)
+    }
+
+    pub fn variable_patterns(&self) -> Vec<String> {
+        like_patterns(&self.variable)
+    }
+}
+
+/// One value in IAMC terms, as exported.
+#[derive(Debug, FromRow)]
+pub struct IamcValue {
+    pub model: String,
+    pub scenario: String,
+    pub region: String,
+    pub variable: String,
+    pub unit: String,
+    pub year: i32,
+    pub value: f64,
+    /// Tells apart scenarios sharing a model and title, which would
+    /// otherwise be merged into one series.
+    pub scenario_id: i32,
+}
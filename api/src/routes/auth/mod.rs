@@ -1,65 +1,76 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use bcrypt::{hash, verify, DEFAULT_COST};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use chrono::{Utc, Duration};
-use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
-use crate::models::{CreateUserRequest, LoginRequest, LoginResponse, User, UserResponse};
+use crate::auth::{issue_token, AuthenticatedUser};
+use crate::models::{CreateUserRequest, LoginRequest, LoginResponse, Role, User, UserResponse};
 use crate::errors::ApiError;
 use crate::config::Config;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: i32,
-    username: String,
-    exp: i64,
-}
-
 #[post("/register")]
 async fn register(
     pool: web::Data<PgPool>,
     user_data: web::Json<CreateUserRequest>,
 ) -> Result<impl Responder, ApiError> {
+    // Hash the password off the async workers and before taking the lock
+    // below, as it is slow
+    let password = user_data.password.clone();
+    let password_hash = web::block(move || hash(password, DEFAULT_COST))
+        .await
+        .map_err(|_| ApiError::InternalError("Failed to hash password".into()))?
+        .map_err(|_| ApiError::InternalError("Failed to hash password".into()))?;
+
+    let mut tx = pool.begin().await.map_err(ApiError::DbError)?;
+
+    // Serialize registrations, so that two concurrent first registrations
+    // cannot both see an empty table and both become admins.
+    sqlx::query!("LOCK TABLE pbtar.users IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::DbError)?;
+
     // Check if user already exists
     let existing_user = sqlx::query!(
         r#"SELECT id FROM pbtar.users WHERE username = $1 OR email = $2"#,
         user_data.username,
         user_data.email
     )
-    .fetch_optional(pool.get_ref())
+    .fetch_optional(&mut *tx)
     .await
-    .map_err(|e| ApiError::DbError(e))?;
+    .map_err(ApiError::DbError)?;
 
     if existing_user.is_some() {
         return Err(ApiError::BadRequestError("Username or email already exists".into()));
     }
 
-    // Hash the password
-    let password_hash = hash(&user_data.password, DEFAULT_COST)
-        .map_err(|_| ApiError::InternalError("Failed to hash password".into()))?;
-
-    // Insert the new user
+    // Insert the new user. The very first account becomes an admin so that
+    // a fresh deployment can be bootstrapped; everyone else starts as a viewer.
     let user = sqlx::query_as!(
         User,
         r#"
-        INSERT INTO pbtar.users (username, email, password_hash)
-        VALUES ($1, $2, $3)
-        RETURNING id, username, email, password_hash, created_at, updated_at
+        INSERT INTO pbtar.users (username, email, password_hash, role)
+        VALUES (
+            $1, $2, $3,
+            CASE WHEN EXISTS (SELECT 1 FROM pbtar.users) THEN 'viewer' ELSE 'admin' END
+        )
+        RETURNING id, username, email, password_hash, role as "role: Role", created_at, updated_at
         "#,
         user_data.username,
         user_data.email,
         password_hash
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| ApiError::DbError(e))?;
+    .map_err(ApiError::DbError)?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
 
     // Create response
     let user_response = UserResponse {
         id: user.id,
         username: user.username,
         email: user.email,
+        role: user.role,
     };
 
     Ok(HttpResponse::Created().json(user_response))
@@ -74,12 +85,16 @@ async fn login(
     // Find the user
     let user = sqlx::query_as!(
         User,
-        r#"SELECT * FROM pbtar.users WHERE username = $1"#,
+        r#"
+        SELECT id, username, email, password_hash, role as "role: Role", created_at, updated_at
+        FROM pbtar.users
+        WHERE username = $1
+        "#,
         login_data.username
     )
     .fetch_optional(pool.get_ref())
     .await
-    .map_err(|e| ApiError::DbError(e))?
+    .map_err(ApiError::DbError)?
     .ok_or_else(|| ApiError::AuthError("Invalid username or password".into()))?;
 
     // Verify password
@@ -91,23 +106,7 @@ async fn login(
     }
 
     // Generate JWT
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::seconds(config.jwt_expiration))
-        .expect("Valid timestamp")
-        .timestamp();
-
-    let claims = Claims {
-        sub: user.id,
-        username: user.username.clone(),
-        exp: expiration,
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
-    )
-    .map_err(|_| ApiError::InternalError("Failed to generate token".into()))?;
+    let token = issue_token(&config, &user)?;
 
     // Create response
     let response = LoginResponse {
@@ -116,15 +115,35 @@ async fn login(
             id: user.id,
             username: user.username,
             email: user.email,
+            role: user.role,
         },
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[get("/me")]
+async fn me(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedUser,
+) -> Result<impl Responder, ApiError> {
+    let user = sqlx::query_as!(
+        UserResponse,
+        r#"SELECT id, username, email, role as "role: Role" FROM pbtar.users WHERE id = $1"#,
+        auth.0.sub
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::AuthError("User no longer exists".into()))?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/auth")
         .service(register)
         .service(login)
+        .service(me)
     );
 }
\ No newline at end of file
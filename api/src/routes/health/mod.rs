@@ -1,5 +1,12 @@
+use std::time::Instant;
+
 use actix_web::{get, HttpResponse, Responder, web};
 use serde::Serialize;
+use sqlx::PgPool;
+use tokio::time::timeout;
+
+use crate::config::Config;
+use crate::db;
 
 #[derive(Serialize)]
 struct HealthResponse {
@@ -8,6 +15,43 @@ struct HealthResponse {
     name: String,
 }
 
+#[derive(Serialize)]
+struct ReadinessResponse {
+    status: &'static str,
+    version: String,
+    database: DatabaseStatus,
+    pool: PoolStats,
+    migrations: MigrationStatus,
+}
+
+#[derive(Serialize)]
+struct DatabaseStatus {
+    status: &'static str,
+    /// Round trip of `SELECT 1`, including waiting for a connection.
+    latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PoolStats {
+    size: u32,
+    idle: usize,
+    in_use: usize,
+    max: u32,
+}
+
+#[derive(Serialize)]
+struct MigrationStatus {
+    status: &'static str,
+    /// Newest migration applied to the database.
+    current: Option<i64>,
+    /// Newest migration built into the API.
+    expected: Option<i64>,
+}
+
+/// Liveness probe: answers as long as the server is up, without touching
+/// the database.
 #[get("/health")]
 async fn health_check() -> impl Responder {
     let response = HealthResponse {
@@ -15,10 +59,80 @@ async fn health_check() -> impl Responder {
         version: env!("CARGO_PKG_VERSION").to_string(),
         name: "Climate Scenarios Database API".to_string(),
     };
-    
+
     HttpResponse::Ok().json(response)
 }
 
+/// Readiness probe: checks that the database answers within
+/// `health_check_timeout` and that its schema is up to date. Responds 503
+/// otherwise.
+#[get("/health/ready")]
+async fn readiness_check(
+    db: web::Data<PgPool>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let pool = db.get_ref();
+    let limit = config.health_check_timeout();
+
+    let started = Instant::now();
+    let ping = timeout(limit, sqlx::query("SELECT 1").execute(pool)).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let database = match ping {
+        Ok(Ok(_)) => DatabaseStatus { status: "ok", latency_ms: Some(latency_ms), error: None },
+        Ok(Err(e)) => DatabaseStatus { status: "unavailable", latency_ms: None, error: Some(e.to_string()) },
+        Err(_) => DatabaseStatus {
+            status: "unavailable",
+            latency_ms: None,
+            error: Some(format!("No answer within {} seconds", limit.as_secs())),
+        },
+    };
+
+    let expected = db::expected_schema_version();
+    let current = if database.status == "ok" {
+        match timeout(limit, db::schema_version(pool)).await {
+            Ok(Ok(version)) => Some(version),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    let migrations = MigrationStatus {
+        status: match current {
+            Some(version) if version >= expected => "ok",
+            Some(_) => "pending",
+            None => "unknown",
+        },
+        current: current.flatten(),
+        expected,
+    };
+
+    let size = pool.size();
+    let idle = pool.num_idle();
+    let stats = PoolStats {
+        size,
+        idle,
+        in_use: (size as usize).saturating_sub(idle),
+        max: pool.options().get_max_connections(),
+    };
+
+    let ready = database.status == "ok" && migrations.status == "ok";
+    let response = ReadinessResponse {
+        status: if ready { "ok" } else { "unavailable" },
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        database,
+        pool: stats,
+        migrations,
+    };
+
+    if ready {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::ServiceUnavailable().json(response)
+    }
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(health_check);
-}
\ No newline at end of file
+    cfg.service(health_check)
+        .service(readiness_check);
+}
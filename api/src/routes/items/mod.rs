@@ -1,80 +1,196 @@
-use actix_web::{web, get, post, put, delete, HttpResponse};
+use actix_web::{web, get, post, put, delete, HttpRequest, HttpResponse};
 use sqlx::PgPool;
-use crate::models::{CreateItemRequest, UpdateItemRequest, ItemResponse};
+use crate::auth::AuthenticatedUser;
+use crate::db::scenarios::{validate_length, validate_required};
+use crate::models::{CreateItemRequest, Item, ItemFilters, ItemStatus, Paginated, Pagination, Role, UpdateItemRequest};
 use crate::errors::ApiError;
 
+/// Longest title the `items.title` column accepts.
+const TITLE_MAX_LENGTH: usize = 255;
+
+/// Checks that an item title is given and fits its column.
+fn validate_title(title: &str) -> Result<(), ApiError> {
+    validate_required("title", title)?;
+    validate_length("title", title, TITLE_MAX_LENGTH)
+}
+
+/// Lists items, most recently updated first, optionally narrowed to one
+/// `status`, in pages of `per_page`.
 #[get("")]
-async fn get_items(_db: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
-    // This is just a placeholder implementation
-    // In a real implementation, you would fetch items from the database
-    Ok(HttpResponse::Ok().json(Vec::<ItemResponse>::new()))
+async fn get_items(
+    req: HttpRequest,
+    query: web::Query<ItemFilters>,
+    db: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+    let pagination = Pagination::new(query.page, query.per_page)?;
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM pbtar.items WHERE $1::varchar IS NULL OR status = $1"#,
+        query.status as Option<ItemStatus>
+    )
+    .fetch_one(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let items = sqlx::query_as!(
+        Item,
+        r#"
+        SELECT id, title, description, status as "status: ItemStatus", created_at, updated_at
+        FROM pbtar.items
+        WHERE $1::varchar IS NULL OR status = $1
+        ORDER BY updated_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        query.status as Option<ItemStatus>,
+        pagination.limit(),
+        pagination.offset()
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::new(items, total, pagination, req.path(), req.query_string())))
 }
 
 #[get("/{id}")]
 async fn get_item_by_id(
     path: web::Path<i32>,
-    _db: web::Data<PgPool>
+    db: web::Data<PgPool>
 ) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
-    // This is just a placeholder implementation
-    // In a real implementation, you would fetch the item from the database
-    Err(ApiError::NotFoundError(format!("Item with id {} not found", id)))
+
+    let item = sqlx::query_as!(
+        Item,
+        r#"
+        SELECT id, title, description, status as "status: ItemStatus", created_at, updated_at
+        FROM pbtar.items
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Item with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(item))
 }
 
 #[post("")]
 async fn create_item(
+    user: AuthenticatedUser,
     item: web::Json<CreateItemRequest>,
-    _db: web::Data<PgPool>
+    db: web::Data<PgPool>
 ) -> Result<HttpResponse, ApiError> {
-    // This is just a placeholder implementation
-    // In a real implementation, you would insert the item into the database
-    Ok(HttpResponse::Created().json(ItemResponse {
-        id: 1,
-        title: item.title.clone(),
-        description: item.description.clone(),
-        status: "active".to_string(),
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-    }))
+    user.require(Role::Curator)?;
+    validate_title(&item.title)?;
+
+    let item = sqlx::query_as!(
+        Item,
+        r#"
+        INSERT INTO pbtar.items (title, description, status)
+        VALUES ($1, $2, $3)
+        RETURNING id, title, description, status as "status: ItemStatus", created_at, updated_at
+        "#,
+        item.title.trim(),
+        item.description,
+        item.status as ItemStatus
+    )
+    .fetch_one(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Created().json(item))
 }
 
 #[put("/{id}")]
 async fn update_item(
+    user: AuthenticatedUser,
     path: web::Path<i32>,
     item: web::Json<UpdateItemRequest>,
-    _db: web::Data<PgPool>
+    db: web::Data<PgPool>
 ) -> Result<HttpResponse, ApiError> {
+    user.require(Role::Curator)?;
+
     let id = path.into_inner();
-    // This is just a placeholder implementation
-    // In a real implementation, you would update the item in the database
-    Ok(HttpResponse::Ok().json(ItemResponse {
+
+    if let Some(title) = &item.title {
+        validate_title(title)?;
+    }
+
+    let item = sqlx::query_as!(
+        Item,
+        r#"
+        UPDATE pbtar.items SET
+            title = COALESCE($2, title),
+            description = CASE WHEN $3 THEN $4 ELSE description END,
+            status = COALESCE($5, status)
+        WHERE id = $1
+        RETURNING id, title, description, status as "status: ItemStatus", created_at, updated_at
+        "#,
         id,
-        title: item.title.clone().unwrap_or_else(|| "Default Title".to_string()),
-        description: item.description.clone(),
-        status: item.status.clone().unwrap_or_else(|| "active".to_string()),
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-    }))
+        item.title.as_deref().map(str::trim),
+        item.description.is_some(),
+        item.description.as_ref().and_then(Option::as_deref),
+        item.status as Option<ItemStatus>
+    )
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Item with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(item))
 }
 
 #[delete("/{id}")]
 async fn delete_item(
+    user: AuthenticatedUser,
     path: web::Path<i32>,
-    _db: web::Data<PgPool>
+    db: web::Data<PgPool>
 ) -> Result<HttpResponse, ApiError> {
+    user.require(Role::Curator)?;
+
     let id = path.into_inner();
-    // This is just a placeholder implementation
-    // In a real implementation, you would delete the item from the database
+
+    sqlx::query_scalar!("DELETE FROM pbtar.items WHERE id = $1 RETURNING id", id)
+        .fetch_optional(db.get_ref())
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::NotFoundError(format!("Item with id {} not found", id)))?;
+
     Ok(HttpResponse::NoContent().finish())
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/items")
+            // Report unknown statuses like every other validation error.
+            .app_data(web::JsonConfig::default().error_handler(|e, _| {
+                ApiError::BadRequestError(e.to_string()).into()
+            }))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| {
+                ApiError::BadRequestError(e.to_string()).into()
+            }))
             .service(get_items)
             .service(get_item_by_id)
             .service(create_item)
             .service(update_item)
             .service(delete_item)
     );
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_title_length() {
+        assert!(validate_title(&"a".repeat(TITLE_MAX_LENGTH)).is_ok());
+        assert!(validate_title(&format!("  {}  ", "a".repeat(TITLE_MAX_LENGTH))).is_ok());
+        assert!(matches!(
+            validate_title(&"a".repeat(TITLE_MAX_LENGTH + 1)),
+            Err(ApiError::BadRequestError(_))
+        ));
+        assert!(matches!(validate_title("   "), Err(ApiError::BadRequestError(_))));
+    }
+}
@@ -0,0 +1,21 @@
+use actix_web::{get, web, HttpResponse};
+use prometheus::TEXT_FORMAT;
+use sqlx::PgPool;
+
+use crate::errors::ApiError;
+use crate::metrics::Metrics;
+
+/// Every metric in the Prometheus text format, for scraping.
+#[get("/metrics")]
+async fn get_metrics(
+    db: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, ApiError> {
+    let body = metrics.render(db.get_ref()).await?;
+
+    Ok(HttpResponse::Ok().content_type(TEXT_FORMAT).body(body))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_metrics);
+}
@@ -1,15 +1,29 @@
 use actix_web::web;
 
+mod auth;
 mod items;
+mod metrics;
 mod health;
+mod publishers;
+mod regions;
 mod scenarios;
+mod sectors;
+mod stakeholders;
+mod users;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg
         .service(
             web::scope("/api")
+                .configure(auth::config)
                 .configure(items::config)
                 .configure(health::config)
+                .configure(publishers::config)
+                .configure(regions::config)
                 .configure(scenarios::config)
-        );
+                .configure(sectors::config)
+                .configure(stakeholders::config)
+                .configure(users::config)
+        )
+        .configure(metrics::config);
 }
\ No newline at end of file
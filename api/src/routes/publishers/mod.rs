@@ -0,0 +1,159 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedUser;
+use crate::db::scenarios::validate_length;
+use crate::db::taxonomy::PUBLISHERS;
+use crate::errors::ApiError;
+use crate::models::{DeleteParams, Publisher, PublisherRequest, Role};
+
+/// Longest website the database stores.
+const WEBSITE_MAX_LENGTH: usize = 255;
+
+fn validate_website(website: Option<&str>) -> Result<(), ApiError> {
+    match website {
+        Some(url) if !(url.starts_with("http://") || url.starts_with("https://")) => Err(
+            ApiError::BadRequestError(format!("Website '{}' must be an http:// or https:// URL", url)),
+        ),
+        Some(url) => validate_length("website", url, WEBSITE_MAX_LENGTH),
+        _ => Ok(()),
+    }
+}
+
+#[get("")]
+async fn list_publishers(
+    db: web::Data<PgPool>,
+) -> Result<impl Responder, ApiError> {
+    let publishers = sqlx::query_as!(
+        Publisher,
+        "SELECT id, name, description, website FROM pbtar.publishers ORDER BY name"
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(publishers))
+}
+
+#[get("/{id}")]
+async fn get_publisher(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+
+    let publisher = sqlx::query_as!(
+        Publisher,
+        "SELECT id, name, description, website FROM pbtar.publishers WHERE id = $1",
+        id
+    )
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Publisher with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(publisher))
+}
+
+#[post("")]
+async fn create_publisher(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    payload: web::Json<PublisherRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    PUBLISHERS.validate_name(&mut tx, &payload.name, None).await?;
+    validate_website(payload.website.as_deref())?;
+
+    let publisher = sqlx::query_as!(
+        Publisher,
+        r#"
+        INSERT INTO pbtar.publishers (name, description, website)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, description, website
+        "#,
+        payload.name.trim(),
+        payload.description,
+        payload.website
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| PUBLISHERS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Created().json(publisher))
+}
+
+#[put("/{id}")]
+async fn update_publisher(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<PublisherRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    PUBLISHERS.lock(&mut tx, id).await?;
+    PUBLISHERS.validate_name(&mut tx, &payload.name, Some(id)).await?;
+    validate_website(payload.website.as_deref())?;
+
+    // Renames reach the scenarios' search vectors through a trigger.
+    let publisher = sqlx::query_as!(
+        Publisher,
+        r#"
+        UPDATE pbtar.publishers SET name = $2, description = $3, website = $4
+        WHERE id = $1
+        RETURNING id, name, description, website
+        "#,
+        id,
+        payload.name.trim(),
+        payload.description,
+        payload.website
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| PUBLISHERS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(publisher))
+}
+
+/// Deletes a publisher. Its scenarios are moved to `reassign_to`; without
+/// one, a publisher with scenarios cannot be deleted.
+#[delete("/{id}")]
+async fn delete_publisher(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    params: web::Query<DeleteParams>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    PUBLISHERS.delete(&mut tx, path.into_inner(), params.reassign_to, &user.0).await?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/publishers")
+            .service(list_publishers)
+            .service(get_publisher)
+            .service(create_publisher)
+            .service(update_publisher)
+            .service(delete_publisher)
+    );
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedUser;
+use crate::db::taxonomy::{lock_regions, validate_region_parent, REGIONS};
+use crate::errors::ApiError;
+use crate::models::{DeleteParams, Region, RegionNode, RegionRequest, Role};
+
+/// Nests `regions` under their parents, starting from the top-level regions.
+fn build_tree(regions: Vec<Region>) -> Vec<RegionNode> {
+    let mut children: HashMap<Option<i32>, Vec<Region>> = HashMap::new();
+    for region in regions {
+        children.entry(region.parent_id).or_default().push(region);
+    }
+
+    fn attach(parent: Option<i32>, children: &mut HashMap<Option<i32>, Vec<Region>>) -> Vec<RegionNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|region| RegionNode {
+                id: region.id,
+                name: region.name,
+                children: attach(Some(region.id), children),
+            })
+            .collect()
+    }
+
+    attach(None, &mut children)
+}
+
+#[get("/tree")]
+async fn get_region_tree(
+    db: web::Data<PgPool>,
+) -> Result<impl Responder, ApiError> {
+    let regions = sqlx::query_as!(
+        Region,
+        "SELECT id, name, parent_id FROM pbtar.regions ORDER BY name"
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(build_tree(regions)))
+}
+
+#[get("")]
+async fn list_regions(
+    db: web::Data<PgPool>,
+) -> Result<impl Responder, ApiError> {
+    let regions = sqlx::query_as!(
+        Region,
+        "SELECT id, name, parent_id FROM pbtar.regions ORDER BY name"
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(regions))
+}
+
+#[get("/{id}")]
+async fn get_region(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+
+    let region = sqlx::query_as!(
+        Region,
+        "SELECT id, name, parent_id FROM pbtar.regions WHERE id = $1",
+        id
+    )
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Region with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(region))
+}
+
+#[post("")]
+async fn create_region(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    payload: web::Json<RegionRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    lock_regions(&mut tx).await?;
+    REGIONS.validate_name(&mut tx, &payload.name, None).await?;
+    validate_region_parent(&mut tx, None, payload.parent_id).await?;
+
+    let region = sqlx::query_as!(
+        Region,
+        "INSERT INTO pbtar.regions (name, parent_id) VALUES ($1, $2) RETURNING id, name, parent_id",
+        payload.name.trim(),
+        payload.parent_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| REGIONS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Created().json(region))
+}
+
+/// Renames a region or moves it under another parent. A region cannot be
+/// moved under itself or any of its subregions.
+#[put("/{id}")]
+async fn update_region(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<RegionRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    lock_regions(&mut tx).await?;
+    REGIONS.lock(&mut tx, id).await?;
+    REGIONS.validate_name(&mut tx, &payload.name, Some(id)).await?;
+    validate_region_parent(&mut tx, Some(id), payload.parent_id).await?;
+
+    let region = sqlx::query_as!(
+        Region,
+        "UPDATE pbtar.regions SET name = $2, parent_id = $3 WHERE id = $1 RETURNING id, name, parent_id",
+        id,
+        payload.name.trim(),
+        payload.parent_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| REGIONS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(region))
+}
+
+/// Deletes a region without subregions. Scenarios and pathway data referring
+/// to it are moved to `reassign_to`; without one, a region in use cannot be
+/// deleted.
+#[delete("/{id}")]
+async fn delete_region(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    params: web::Query<DeleteParams>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    lock_regions(&mut tx).await?;
+    REGIONS.lock(&mut tx, id).await?;
+
+    let subregions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM pbtar.regions WHERE parent_id = $1"#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    if subregions > 0 {
+        return Err(ApiError::ConflictError(format!(
+            "Region {} has {} subregion(s); move or delete them first",
+            id, subregions
+        )));
+    }
+
+    REGIONS.delete(&mut tx, id, params.reassign_to, &user.0).await?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/regions")
+            .service(list_regions)
+            .service(get_region_tree)
+            .service(get_region)
+            .service(create_region)
+            .service(update_region)
+            .service(delete_region)
+    );
+}
\ No newline at end of file
@@ -1,112 +1,783 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
 
+use actix_web::http::header;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse, Responder};
+use futures::channel::mpsc;
+use futures::{SinkExt, TryStreamExt};
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::auth::AuthenticatedUser;
+use crate::db::scenarios;
 use crate::errors::ApiError;
-use crate::models::{ScenarioDetail, ScenarioFilters, ScenarioListItem};
+use crate::metrics::Metrics;
+use crate::models::{
+    AlignedTimeseries, AlignedValue, CompareParams, CreateScenarioRequest, ExportFormat, Facet,
+    FacetValue, FieldComparison, FilterFacets, FilterOptionsParams, IamcImportParams, IamcValue,
+    ImportParams, MatchMode, Paginated, Pagination, RelationDirection, RelationKind,
+    RevisionAction, Role, ScenarioComparison, ScenarioDetail, ScenarioExportRow, ScenarioFilters,
+    ScenarioListItem, ScenarioQuery, ScenarioRelation, ScenarioRevision, ScenarioSnapshot,
+    SetComparison, TemperatureTarget, Timeseries, TimeseriesExportParams, TimeseriesFilters,
+    TimeseriesValue, UpdateScenarioRequest, Variable,
+};
 
-#[get("")]
-async fn list_scenarios(
-    db: web::Data<PgPool>,
-    query: web::Query<ScenarioFilters>,
-) -> Result<impl Responder, ApiError> {
-    let mut sql = String::from(
-        "SELECT s.id, s.title, s.type as type_name, s.temperature_target, 
-        s.description, p.name as publisher, s.published_date, s.target_year
-        FROM pbtar.scenarios s
-        LEFT JOIN pbtar.publishers p ON s.publisher_id = p.id
-        WHERE 1=1"
+/// Appends the `FROM` and `WHERE` clauses shared by the list and count queries.
+/// When searching, the parsed query is exposed to the select list as `query`.
+fn push_from(builder: &mut QueryBuilder<'_, Postgres>, filters: &ScenarioFilters) {
+    builder.push(
+        " FROM pbtar.scenarios s
+        LEFT JOIN pbtar.publishers p ON s.publisher_id = p.id"
+    );
+
+    if let Some(terms) = filters.search_terms() {
+        builder
+            .push(" CROSS JOIN websearch_to_tsquery('english', ")
+            .push_bind(terms.to_string())
+            .push(") AS query");
+    }
+
+    builder.push(" WHERE 1=1");
+    push_filters(builder, filters);
+}
+
+/// Recursive CTEs pairing each root region with itself and, as
+/// `filters` asks, its subregions and/or containing regions, walking
+/// `regions.parent_id`. The pairs end up in `expanded(root, id)`. Roots are
+/// `roots`, or every region when `None`.
+fn push_expanded_regions(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    filters: &ScenarioFilters,
+    roots: Option<Vec<i32>>,
+) {
+    let push_roots = |builder: &mut QueryBuilder<'_, Postgres>| {
+        builder.push("SELECT id, id FROM pbtar.regions");
+        if let Some(roots) = roots.clone() {
+            builder.push(" WHERE id = ANY(").push_bind(roots).push(")");
+        }
+    };
+
+    builder.push(
+        "WITH RECURSIVE descendants(root, id) AS (
+                ",
+    );
+    push_roots(builder);
+    if filters.include_subregions {
+        builder.push(
+            " UNION
+                SELECT d.root, r.id FROM descendants d JOIN pbtar.regions r ON r.parent_id = d.id",
+        );
+    }
+    builder.push(
+        "
+            ), ancestors(root, id) AS (
+                ",
+    );
+    push_roots(builder);
+    if filters.include_parent_regions {
+        builder.push(
+            " UNION
+                SELECT a.root, r.parent_id FROM ancestors a JOIN pbtar.regions r ON r.id = a.id
+                WHERE r.parent_id IS NOT NULL",
+        );
+    }
+    builder.push(
+        "
+            ), expanded AS (
+                SELECT root, id FROM descendants UNION SELECT root, id FROM ancestors
+            )",
+    );
+}
+
+/// Region filter that also accepts subregions and/or containing regions of
+/// each requested region. In `all` mode every requested region must be
+/// matched by itself or one of its relatives.
+fn push_region_hierarchy_filter(builder: &mut QueryBuilder<'_, Postgres>, filters: &ScenarioFilters) {
+    if filters.region_id.is_empty() {
+        return;
+    }
+
+    let mut ids = filters.region_id.clone();
+    ids.sort_unstable();
+    ids.dedup();
+    let required = ids.len() as i64;
+
+    builder.push(" AND s.id IN (");
+    push_expanded_regions(builder, filters, Some(ids));
+    builder.push(
+        "
+            SELECT sr.scenario_id
+            FROM pbtar.scenario_regions sr
+            JOIN expanded e ON sr.region_id = e.id",
     );
 
-    let mut params: Vec<String> = Vec::new();
-    let mut values: Vec<&(dyn sqlx::Encode<sqlx::Postgres> + Sync)> = Vec::new();
-    let mut param_count = 1;
+    if filters.region_match == MatchMode::All {
+        builder
+            .push(" GROUP BY sr.scenario_id HAVING COUNT(DISTINCT e.root) = ")
+            .push_bind(required);
+    }
 
-    if let Some(publisher_id) = &query.publisher_id {
-        sql.push_str(&format!(" AND s.publisher_id = ${}", param_count));
-        params.push(publisher_id.to_string());
-        values.push(publisher_id);
-        param_count += 1;
+    builder.push(")");
+}
+
+/// Appends one `AND ...` condition per supplied filter, binding every value.
+fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, filters: &ScenarioFilters) {
+    if filters.search_terms().is_some() {
+        builder.push(" AND s.search_vector @@ query");
     }
 
-    if let Some(region_id) = &query.region_id {
-        sql.push_str(&format!(" AND s.id IN (SELECT scenario_id FROM pbtar.scenario_regions WHERE region_id = ${})", param_count));
-        params.push(region_id.to_string());
-        values.push(region_id);
-        param_count += 1;
+    if !filters.publisher_id.is_empty() {
+        builder
+            .push(" AND s.publisher_id = ANY(")
+            .push_bind(filters.publisher_id.clone())
+            .push(")");
     }
 
-    if let Some(stakeholder_id) = &query.stakeholder_id {
-        sql.push_str(&format!(" AND s.id IN (SELECT scenario_id FROM pbtar.scenario_stakeholders WHERE stakeholder_id = ${})", param_count));
-        params.push(stakeholder_id.to_string());
-        values.push(stakeholder_id);
-        param_count += 1;
+    if !filters.exclude_publisher_id.is_empty() {
+        builder
+            .push(" AND (s.publisher_id IS NULL OR s.publisher_id <> ALL(")
+            .push_bind(filters.exclude_publisher_id.clone())
+            .push("))");
     }
 
-    if let Some(sector_id) = &query.sector_id {
-        sql.push_str(&format!(" AND s.id IN (SELECT scenario_id FROM pbtar.scenario_sectors WHERE sector_id = ${})", param_count));
-        params.push(sector_id.to_string());
-        values.push(sector_id);
-        param_count += 1;
+    if filters.include_subregions || filters.include_parent_regions {
+        push_region_hierarchy_filter(builder, filters);
+    } else {
+        scenarios::REGIONS.push_filter(builder, &filters.region_id, filters.region_match);
     }
+    scenarios::REGIONS.push_exclusion(builder, &filters.exclude_region_id);
+
+    scenarios::STAKEHOLDERS.push_filter(builder, &filters.stakeholder_id, filters.stakeholder_match);
+    scenarios::STAKEHOLDERS.push_exclusion(builder, &filters.exclude_stakeholder_id);
 
-    if let Some(type_name) = &query.type_name {
-        sql.push_str(&format!(" AND s.type = ${}", param_count));
-        params.push(type_name.to_string());
-        values.push(type_name);
-        param_count += 1;
+    scenarios::SECTORS.push_filter(builder, &filters.sector_id, filters.sector_match);
+    scenarios::SECTORS.push_exclusion(builder, &filters.exclude_sector_id);
+
+    if let Some(type_name) = &filters.type_name {
+        builder.push(" AND s.type = ").push_bind(type_name.clone());
     }
 
-    if let Some(temperature_target) = &query.temperature_target {
-        sql.push_str(&format!(" AND s.temperature_target = ${}", param_count));
-        params.push(temperature_target.to_string());
-        values.push(temperature_target);
-        param_count += 1;
+    if let Some(temperature_target) = &filters.temperature_target {
+        builder.push(" AND s.temperature_target = ").push_bind(temperature_target.clone());
     }
 
-    if let Some(year_from) = &query.year_from {
-        sql.push_str(&format!(" AND s.target_year >= ${}", param_count));
-        params.push(year_from.to_string());
-        values.push(year_from);
-        param_count += 1;
+    if let Some(temp_min) = filters.temp_min {
+        builder.push(" AND s.temperature_lower >= ").push_bind(temp_min);
     }
 
-    if let Some(year_to) = &query.year_to {
-        sql.push_str(&format!(" AND s.target_year <= ${}", param_count));
-        params.push(year_to.to_string());
-        values.push(year_to);
-        param_count += 1;
+    if let Some(temp_max) = filters.temp_max {
+        builder.push(" AND s.temperature_upper <= ").push_bind(temp_max);
     }
 
-    sql.push_str(" ORDER BY s.published_date DESC");
+    if let Some(year_from) = filters.year_from {
+        builder.push(" AND s.target_year >= ").push_bind(year_from);
+    }
 
-    let scenarios = sqlx::query_as::<_, ScenarioListItem>(&sql)
+    if let Some(year_to) = filters.year_to {
+        builder.push(" AND s.target_year <= ").push_bind(year_to);
+    }
+
+    if filters.latest_only {
+        builder.push(
+            " AND NOT EXISTS (SELECT 1 FROM pbtar.scenario_relations sr \
+             WHERE sr.related_scenario_id = s.id AND sr.relation = 'supersedes')",
+        );
+    }
+}
+
+const DEFAULT_ORDER: &str = "s.published_date DESC NULLS LAST";
+
+/// Maps a public sort key onto the column it orders by. Only these
+/// expressions ever reach the generated SQL.
+fn sort_column(key: &str) -> Option<&'static str> {
+    match key {
+        "title" => Some("s.title"),
+        "publisher" => Some("p.name"),
+        "published_date" => Some("s.published_date"),
+        "target_year" => Some("s.target_year"),
+        "temperature_target" => Some("COALESCE(s.temperature_upper, s.temperature_lower)"),
+        _ => None,
+    }
+}
+
+/// Builds the `ORDER BY` clause for a `sort` parameter. `relevance` orders by
+/// search rank when searching and falls back to newest-first otherwise; it is
+/// also the default. `s.id` is always appended as a tie-breaker so that pages
+/// are stable.
+fn order_by(sort: Option<&str>, searching: bool) -> Result<String, ApiError> {
+    let relevance = if searching {
+        format!("rank DESC, {}", DEFAULT_ORDER)
+    } else {
+        DEFAULT_ORDER.to_string()
+    };
+
+    let mut terms = Vec::new();
+
+    for key in sort.unwrap_or_default().split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        let (key, descending) = match key.strip_prefix('-') {
+            Some(key) => (key, true),
+            None => (key, false),
+        };
+
+        if key == "relevance" {
+            terms.push(relevance.clone());
+            continue;
+        }
+
+        let column = sort_column(key).ok_or_else(|| {
+            ApiError::BadRequestError(format!(
+                "Unknown sort key '{}'; expected one of title, publisher, published_date, \
+                 target_year, temperature_target, relevance",
+                key
+            ))
+        })?;
+
+        let direction = if descending { "DESC" } else { "ASC" };
+        terms.push(format!("{} {} NULLS LAST", column, direction));
+    }
+
+    if terms.is_empty() {
+        terms.push(relevance);
+    }
+    terms.push("s.id DESC".to_string());
+
+    Ok(format!(" ORDER BY {}", terms.join(", ")))
+}
+
+/// Search rank and highlighted snippets, selected alongside the list columns
+/// when searching. Matched terms are delimited by `HIGHLIGHT_START` and
+/// `HIGHLIGHT_STOP` (removed from the text beforehand), which
+/// `ScenarioListItem::mark_highlights` turns into `<mark>` tags once the
+/// text is escaped.
+const SEARCH_COLUMNS: &str = ", ts_rank(s.search_vector, query) AS rank,
+    ts_headline('english', translate(s.title, chr(2) || chr(3), ''), query,
+        'HighlightAll=true, StartSel=' || chr(2) || ', StopSel=' || chr(3)) AS title_highlight,
+    ts_headline('english', translate(COALESCE(s.description, ''), chr(2) || chr(3), ''), query,
+        'MaxFragments=2, MinWords=10, MaxWords=30, StartSel=' || chr(2) || ', StopSel=' || chr(3))
+        AS description_highlight";
+
+#[get("")]
+async fn list_scenarios(
+    db: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let query = ScenarioFilters::from_query(req.query_string())?;
+    metrics.record_search(&query);
+    let order = order_by(query.sort.as_deref(), query.search_terms().is_some())?;
+
+    let format = query.format.unwrap_or_else(|| {
+        req.headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(ExportFormat::from_accept)
+            .unwrap_or_default()
+    });
+    if format != ExportFormat::Json {
+        return export_scenarios(db.get_ref(), &query, &order, format).await;
+    }
+
+    let pagination = Pagination::new(query.page, query.per_page)?;
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*)");
+    push_from(&mut count, &query);
+
+    let total: i64 = count
+        .build_query_scalar()
+        .fetch_one(db.get_ref())
+        .await
+        .map_err(ApiError::DbError)?;
+
+    let mut sql = QueryBuilder::new(
+        "SELECT s.id, s.title, s.type as type_name, s.temperature_target,
+        s.temperature_lower, s.temperature_upper, s.temperature_probability, s.temperature_overshoot,
+        s.description, p.name as publisher, s.published_date, s.target_year"
+    );
+    if query.search_terms().is_some() {
+        sql.push(SEARCH_COLUMNS);
+    }
+    push_from(&mut sql, &query);
+
+    sql.push(order)
+        .push(" LIMIT ")
+        .push_bind(pagination.limit())
+        .push(" OFFSET ")
+        .push_bind(pagination.offset());
+
+    let mut scenarios = sql
+        .build_query_as::<ScenarioListItem>()
         .fetch_all(db.get_ref())
         .await
-        .map_err(|e| ApiError::DbError(e))?;
+        .map_err(ApiError::DbError)?;
+    scenarios.iter_mut().for_each(ScenarioListItem::mark_highlights);
+
+    let page = Paginated::new(scenarios, total, pagination, req.path(), req.query_string());
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// The query listing every scenario matching `filters` in `order`, with the
+/// columns of an export.
+fn export_query(filters: &ScenarioFilters, order: &str) -> QueryBuilder<'static, Postgres> {
+    let mut sql = QueryBuilder::new(
+        "SELECT s.id, s.title, s.type as type_name, s.temperature_target,
+        s.temperature_lower, s.temperature_upper, s.temperature_probability, s.temperature_overshoot,
+        s.description, p.name as publisher, s.published_date, s.target_year,
+        COALESCE((
+            SELECT string_agg(r.name, '; ' ORDER BY r.name)
+            FROM pbtar.scenario_regions sr JOIN pbtar.regions r ON r.id = sr.region_id
+            WHERE sr.scenario_id = s.id
+        ), '') AS regions,
+        COALESCE((
+            SELECT string_agg(se.name, '; ' ORDER BY se.name)
+            FROM pbtar.scenario_sectors ss JOIN pbtar.sectors se ON se.id = ss.sector_id
+            WHERE ss.scenario_id = s.id
+        ), '') AS sectors,
+        COALESCE((
+            SELECT string_agg(st.name, '; ' ORDER BY st.name)
+            FROM pbtar.scenario_stakeholders sst JOIN pbtar.stakeholders st ON st.id = sst.stakeholder_id
+            WHERE sst.scenario_id = s.id
+        ), '') AS stakeholders"
+    );
+    if filters.search_terms().is_some() {
+        sql.push(", ts_rank(s.search_vector, query) AS rank");
+    }
+    push_from(&mut sql, filters);
+    sql.push(order);
+    sql
+}
+
+/// CSV lines are sent to the client in chunks of about this many bytes.
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes the CSV export of `filters` into `sender` as the rows arrive from
+/// the database. Stops early if the client goes away.
+async fn stream_scenarios_csv(
+    db: &PgPool,
+    filters: &ScenarioFilters,
+    order: &str,
+    sender: &mut mpsc::Sender<Result<web::Bytes, actix_web::Error>>,
+) -> Result<(), ApiError> {
+    let mut chunk = crate::export::scenarios_csv_header()?;
+
+    let mut sql = export_query(filters, order);
+    let mut rows = sql.build_query_as::<ScenarioExportRow>().fetch(db);
+    while let Some(row) = rows.try_next().await.map_err(ApiError::DbError)? {
+        chunk.extend(crate::export::scenarios_csv_record(&row)?);
+        if chunk.len() >= EXPORT_CHUNK_SIZE && sender.send(Ok(std::mem::take(&mut chunk).into())).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    let _ = sender.send(Ok(chunk.into())).await;
+    Ok(())
+}
 
-    Ok(HttpResponse::Ok().json(scenarios))
+/// Responds with every scenario matching `query`, in list order, as a CSV or
+/// XLSX download. Pagination parameters are ignored. CSV is streamed as the
+/// rows are read; XLSX is built in memory and refused beyond
+/// `MAX_XLSX_ROWS` scenarios.
+async fn export_scenarios(
+    db: &PgPool,
+    query: &ScenarioFilters,
+    order: &str,
+    format: ExportFormat,
+) -> Result<HttpResponse, ApiError> {
+    let filename = format!(
+        "scenarios-{}.{}",
+        chrono::Utc::now().format("%Y-%m-%d"),
+        format.extension()
+    );
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(format.content_type())
+        .insert_header(header::ContentDisposition::attachment(filename));
+
+    if format != ExportFormat::Xlsx {
+        let (mut sender, receiver) = mpsc::channel(4);
+        let (db, filters, order) = (db.clone(), query.clone(), order.to_string());
+        actix_web::rt::spawn(async move {
+            if let Err(e) = stream_scenarios_csv(&db, &filters, &order, &mut sender).await {
+                log::error!("Failed to stream the scenario export: {}", e);
+                let _ = sender.send(Err(e.into())).await;
+            }
+        });
+
+        return Ok(response.streaming(receiver));
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*)");
+    push_from(&mut count, query);
+
+    let total: i64 = count
+        .build_query_scalar()
+        .fetch_one(db)
+        .await
+        .map_err(ApiError::DbError)?;
+    if total > crate::export::MAX_XLSX_ROWS {
+        return Err(ApiError::BadRequestError(format!(
+            "XLSX exports are limited to {} scenarios but {} match; narrow the filters or export CSV instead",
+            crate::export::MAX_XLSX_ROWS, total
+        )));
+    }
+
+    let rows = export_query(query, order)
+        .build_query_as::<ScenarioExportRow>()
+        .fetch_all(db)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(response.body(crate::export::scenarios_xlsx(&rows)?))
+}
+
+/// `ScenarioDetail` fields compared one by one; the taxonomies and variables
+/// are compared as sets instead.
+const COMPARED_FIELDS: [&str; 12] = [
+    "title",
+    "type_name",
+    "model",
+    "temperature_target",
+    "temperature_lower",
+    "temperature_upper",
+    "temperature_probability",
+    "temperature_overshoot",
+    "description",
+    "published_date",
+    "target_year",
+    "publisher",
+];
+
+/// Compares scenarios side by side: their fields aligned, the overlap of their
+/// regions, sectors and stakeholders, and the yearly values of every series
+/// they all have data for.
+#[get("/compare")]
+async fn compare_scenarios(
+    db: web::Data<PgPool>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let params = CompareParams::from_query(req.query_string())?;
+
+    let mut scenarios = Vec::with_capacity(params.ids.len());
+    for id in &params.ids {
+        scenarios.push(load_scenario_detail(db.get_ref(), *id).await?);
+    }
+
+    let documents: Vec<serde_json::Value> = scenarios
+        .iter()
+        .map(|scenario| serde_json::to_value(scenario).unwrap_or_default())
+        .collect();
+    let fields = COMPARED_FIELDS
+        .iter()
+        .map(|field| {
+            let values: Vec<serde_json::Value> = documents.iter().map(|doc| doc[field].clone()).collect();
+            FieldComparison {
+                field: field.to_string(),
+                same: values.windows(2).all(|pair| pair[0] == pair[1]),
+                values,
+            }
+        })
+        .collect();
+
+    let regions: Vec<&[_]> = scenarios.iter().map(|s| s.regions.as_slice()).collect();
+    let sectors: Vec<&[_]> = scenarios.iter().map(|s| s.sectors.as_slice()).collect();
+    let stakeholders: Vec<&[_]> = scenarios.iter().map(|s| s.stakeholders.as_slice()).collect();
+
+    let comparison = ScenarioComparison {
+        fields,
+        regions: SetComparison::new(&regions, |r| r.id),
+        sectors: SetComparison::new(&sectors, |s| s.id),
+        stakeholders: SetComparison::new(&stakeholders, |s| s.id),
+        timeseries: align_timeseries(db.get_ref(), &params.ids).await?,
+        scenarios,
+    };
+
+    Ok(HttpResponse::Ok().json(comparison))
 }
 
+/// Aligns by year the series that every one of `ids` has values for.
+async fn align_timeseries(db: &PgPool, ids: &[i32]) -> Result<Vec<AlignedTimeseries>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            t.scenario_id,
+            v.id as variable_id, v.name as variable, u.name as unit,
+            r.id as region_id, r.name as region,
+            se.id as "sector_id?", se.name as "sector?",
+            t.year, t.value
+        FROM pbtar.timeseries t
+        JOIN pbtar.variables v ON v.id = t.variable_id
+        JOIN pbtar.units u ON u.id = v.unit_id
+        JOIN pbtar.regions r ON r.id = t.region_id
+        LEFT JOIN pbtar.sectors se ON se.id = t.sector_id
+        WHERE t.scenario_id = ANY($1)
+        ORDER BY v.name, r.name, se.name NULLS FIRST, t.year
+        "#,
+        ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let position: HashMap<i32, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    // Rows arrive grouped by series, so each series is built up in turn.
+    let mut aligned = Vec::new();
+    let mut rows = rows.into_iter().peekable();
+    while let Some(first) = rows.next() {
+        let key = (first.variable_id, first.region_id, first.sector_id);
+        let mut values: BTreeMap<i32, Vec<Option<f64>>> = BTreeMap::new();
+        let mut present = BTreeSet::new();
+
+        let mut record = |scenario_id: i32, year: i32, value: f64| {
+            let index = position[&scenario_id];
+            values.entry(year).or_insert_with(|| vec![None; ids.len()])[index] = Some(value);
+            present.insert(index);
+        };
+        record(first.scenario_id, first.year, first.value);
+        while let Some(row) = rows.next_if(|row| (row.variable_id, row.region_id, row.sector_id) == key) {
+            record(row.scenario_id, row.year, row.value);
+        }
+
+        if present.len() == ids.len() {
+            aligned.push(AlignedTimeseries {
+                variable_id: first.variable_id,
+                variable: first.variable,
+                unit: first.unit,
+                region_id: first.region_id,
+                region: first.region,
+                sector_id: first.sector_id,
+                sector: first.sector,
+                values: values
+                    .into_iter()
+                    .map(|(year, values)| AlignedValue { year, values })
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(aligned)
+}
+
+/// Returns a scenario, or with `?revision=N` the scenario as it was at that
+/// revision. Past revisions remain available after a scenario is deleted.
 #[get("/{id}")]
 async fn get_scenario(
     db: web::Data<PgPool>,
     path: web::Path<i32>,
+    query: web::Query<ScenarioQuery>,
 ) -> Result<impl Responder, ApiError> {
     let id = path.into_inner();
 
+    let response = match query.revision {
+        Some(revision) => load_scenario_revision(db.get_ref(), id, revision).await?,
+        None => load_scenario_detail(db.get_ref(), id).await?,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Lists the revisions of a scenario, newest first, with the fields each one
+/// changed.
+#[get("/{id}/history")]
+async fn get_scenario_history(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT revision, action as "action: RevisionAction", user_id, username, created_at,
+            snapshot as "snapshot: Json<ScenarioSnapshot>"
+        FROM pbtar.scenario_revisions
+        WHERE scenario_id = $1
+        ORDER BY revision
+        "#,
+        id
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    if rows.is_empty() {
+        return Err(ApiError::NotFoundError(format!("Scenario with id {} not found", id)));
+    }
+
+    let mut history = Vec::with_capacity(rows.len());
+    let mut previous: Option<ScenarioSnapshot> = None;
+    for row in rows {
+        let snapshot = row.snapshot.0;
+        history.push(ScenarioRevision {
+            revision: row.revision,
+            action: row.action,
+            user_id: row.user_id,
+            username: row.username,
+            created_at: row.created_at,
+            changes: snapshot.changes_since(previous.as_ref()),
+        });
+        previous = Some(snapshot);
+    }
+    history.reverse();
+
+    Ok(HttpResponse::Ok().json(history))
+}
+
+/// Rebuilds a `ScenarioDetail` from a stored revision, resolving the
+/// referenced publisher and taxonomy entries that still exist.
+async fn load_scenario_revision(db: &PgPool, id: i32, revision: i32) -> Result<ScenarioDetail, ApiError> {
+    let snapshot = sqlx::query_scalar!(
+        r#"
+        SELECT snapshot as "snapshot: Json<ScenarioSnapshot>"
+        FROM pbtar.scenario_revisions
+        WHERE scenario_id = $1 AND revision = $2
+        "#,
+        id,
+        revision
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Revision {} of scenario {} not found", revision, id)))?
+    .0;
+
+    let publisher = sqlx::query_as!(
+        crate::models::Publisher,
+        "SELECT id, name, description, website FROM pbtar.publishers WHERE id = $1",
+        snapshot.publisher_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let regions = sqlx::query_as!(
+        crate::models::Region,
+        "SELECT id, name, parent_id FROM pbtar.regions WHERE id = ANY($1) ORDER BY id",
+        &snapshot.region_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let stakeholders = sqlx::query_as!(
+        crate::models::Stakeholder,
+        r#"SELECT id, name, type as "type_name" FROM pbtar.stakeholders WHERE id = ANY($1) ORDER BY id"#,
+        &snapshot.stakeholder_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let sectors = sqlx::query_as!(
+        crate::models::Sector,
+        "SELECT id, name FROM pbtar.sectors WHERE id = ANY($1) ORDER BY id",
+        &snapshot.sector_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let targets: Vec<i32> = snapshot.relations.iter().map(|r| r.scenario_id).collect();
+    let titles = sqlx::query!("SELECT id, title FROM pbtar.scenarios WHERE id = ANY($1)", &targets)
+        .fetch_all(db)
+        .await
+        .map_err(ApiError::DbError)?;
+    // Relations to scenarios deleted since are kept, without a title.
+    let relations = snapshot
+        .relations
+        .iter()
+        .map(|relation| ScenarioRelation {
+            relation: relation.relation,
+            direction: RelationDirection::Outgoing,
+            scenario_id: relation.scenario_id,
+            title: titles
+                .iter()
+                .find(|t| t.id == relation.scenario_id)
+                .map(|t| t.title.clone()),
+        })
+        .collect();
+
+    let temperature = snapshot
+        .temperature_target
+        .as_deref()
+        .map(TemperatureTarget::parse)
+        .unwrap_or_default();
+
+    Ok(ScenarioDetail {
+        id,
+        title: snapshot.title,
+        type_name: snapshot.type_name,
+        model: snapshot.model,
+        temperature_target: snapshot.temperature_target,
+        temperature_lower: temperature.lower,
+        temperature_upper: temperature.upper,
+        temperature_probability: temperature.probability,
+        temperature_overshoot: temperature.overshoot,
+        description: snapshot.description,
+        published_date: snapshot.published_date,
+        target_year: snapshot.target_year,
+        publisher,
+        regions,
+        stakeholders,
+        sectors,
+        relations,
+        // Pathway data is not versioned.
+        variables: Vec::new(),
+    })
+}
+
+/// Relations held by scenario `id` and relations other scenarios hold to it.
+async fn load_relations(db: &PgPool, id: i32) -> Result<Vec<ScenarioRelation>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT r.relation as "relation!: RelationKind", true as "outgoing!",
+            s.id as "scenario_id!", s.title as "title!", s.published_date
+        FROM pbtar.scenario_relations r
+        JOIN pbtar.scenarios s ON s.id = r.related_scenario_id
+        WHERE r.scenario_id = $1
+        UNION ALL
+        SELECT r.relation, false, s.id, s.title, s.published_date
+        FROM pbtar.scenario_relations r
+        JOIN pbtar.scenarios s ON s.id = r.scenario_id
+        WHERE r.related_scenario_id = $1
+        ORDER BY 1, 2 DESC, 5 DESC NULLS LAST, 3
+        "#,
+        id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ScenarioRelation {
+            relation: row.relation,
+            direction: if row.outgoing {
+                RelationDirection::Outgoing
+            } else {
+                RelationDirection::Incoming
+            },
+            scenario_id: row.scenario_id,
+            title: Some(row.title),
+        })
+        .collect())
+}
+
+async fn load_scenario_detail(db: &PgPool, id: i32) -> Result<ScenarioDetail, ApiError> {
     let scenario = sqlx::query!(
         r#"
         SELECT 
-            s.id, s.title, s.type as "type_name", s.temperature_target, 
+            s.id, s.title, s.type as "type_name", s.model, s.temperature_target,
+            s.temperature_lower, s.temperature_upper, s.temperature_probability, s.temperature_overshoot,
             s.description, s.published_date, s.target_year,
-            p.id as "publisher_id", p.name as "publisher_name", p.description as "publisher_description"
+            p.id as "publisher_id?", p.name as "publisher_name?", p.description as "publisher_description",
+            p.website as "publisher_website"
         FROM pbtar.scenarios s
         LEFT JOIN pbtar.publishers p ON s.publisher_id = p.id
         WHERE s.id = $1
         "#,
         id
     )
-    .fetch_optional(db.get_ref())
+    .fetch_optional(db)
     .await
     .map_err(|e| ApiError::DbError(e))?;
 
@@ -124,7 +795,7 @@ async fn get_scenario(
         "#,
         id
     )
-    .fetch_all(db.get_ref())
+    .fetch_all(db)
     .await
     .map_err(|e| ApiError::DbError(e))?;
 
@@ -137,7 +808,7 @@ async fn get_scenario(
         "#,
         id
     )
-    .fetch_all(db.get_ref())
+    .fetch_all(db)
     .await
     .map_err(|e| ApiError::DbError(e))?;
 
@@ -150,25 +821,50 @@ async fn get_scenario(
         "#,
         id
     )
-    .fetch_all(db.get_ref())
+    .fetch_all(db)
     .await
     .map_err(|e| ApiError::DbError(e))?;
 
-    let publisher = if scenario.publisher_id > 0 {
-        Some(crate::models::Publisher {
-            id: scenario.publisher_id,
-            name: scenario.publisher_name,
+    let relations = load_relations(db, id).await?;
+
+    let variables = sqlx::query_as!(
+        Variable,
+        r#"
+        SELECT v.id, v.name, u.name as unit, v.description
+        FROM pbtar.variables v
+        JOIN pbtar.units u ON u.id = v.unit_id
+        WHERE EXISTS (
+            SELECT 1 FROM pbtar.timeseries t
+            WHERE t.variable_id = v.id AND t.scenario_id = $1
+        )
+        ORDER BY v.name
+        "#,
+        id
+    )
+    .fetch_all(db)
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let publisher = match (scenario.publisher_id, scenario.publisher_name) {
+        (Some(id), Some(name)) => Some(crate::models::Publisher {
+            id,
+            name,
             description: scenario.publisher_description,
-        })
-    } else {
-        None
+            website: scenario.publisher_website,
+        }),
+        _ => None,
     };
 
     let response = ScenarioDetail {
         id: scenario.id,
         title: scenario.title,
         type_name: scenario.type_name,
+        model: scenario.model,
         temperature_target: scenario.temperature_target,
+        temperature_lower: scenario.temperature_lower,
+        temperature_upper: scenario.temperature_upper,
+        temperature_probability: scenario.temperature_probability,
+        temperature_overshoot: scenario.temperature_overshoot,
         description: scenario.description,
         published_date: scenario.published_date,
         target_year: scenario.target_year,
@@ -196,14 +892,463 @@ async fn get_scenario(
                 name: s.name,
             })
             .collect(),
+        relations,
+        variables,
     };
 
+    Ok(response)
+}
+
+#[post("")]
+async fn create_scenario(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    payload: web::Json<CreateScenarioRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+    let id = scenarios::insert_scenario(&mut tx, &payload).await?;
+    scenarios::record_revision(&mut tx, id, RevisionAction::Create, Some(&user.0)).await?;
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    let response = load_scenario_detail(db.get_ref(), id).await?;
+
+    Ok(HttpResponse::Created().json(response))
+}
+
+/// Returns the scenario's pathway data as one series per variable, region
+/// and sector, each holding its values in year order.
+#[get("/{id}/timeseries")]
+async fn get_scenario_timeseries(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+    let filters = TimeseriesFilters::from_query(req.query_string())?;
+
+    sqlx::query_scalar!("SELECT id FROM pbtar.scenarios WHERE id = $1", id)
+        .fetch_optional(db.get_ref())
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::NotFoundError(format!("Scenario with id {} not found", id)))?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            v.id as variable_id, v.name as variable, u.name as unit,
+            r.id as region_id, r.name as region,
+            se.id as "sector_id?", se.name as "sector?",
+            t.year, t.value
+        FROM pbtar.timeseries t
+        JOIN pbtar.variables v ON v.id = t.variable_id
+        JOIN pbtar.units u ON u.id = v.unit_id
+        JOIN pbtar.regions r ON r.id = t.region_id
+        LEFT JOIN pbtar.sectors se ON se.id = t.sector_id
+        WHERE t.scenario_id = $1
+            AND (cardinality($2::int[]) = 0 OR t.variable_id = ANY($2))
+            AND (cardinality($3::text[]) = 0 OR v.name LIKE ANY($3))
+            AND (cardinality($4::int[]) = 0 OR t.region_id = ANY($4))
+            AND (cardinality($5::int[]) = 0 OR t.sector_id = ANY($5))
+            AND ($6::int IS NULL OR t.year >= $6)
+            AND ($7::int IS NULL OR t.year <= $7)
+        ORDER BY v.name, r.name, se.name NULLS FIRST, t.year
+        "#,
+        id,
+        &filters.variable_id,
+        &filters.variable_patterns(),
+        &filters.region_id,
+        &filters.sector_id,
+        filters.year_from,
+        filters.year_to
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let mut series: Vec<Timeseries> = Vec::new();
+    for row in rows {
+        let value = TimeseriesValue {
+            year: row.year,
+            value: row.value,
+        };
+
+        match series.last_mut() {
+            Some(last)
+                if last.variable_id == row.variable_id
+                    && last.region_id == row.region_id
+                    && last.sector_id == row.sector_id =>
+            {
+                last.values.push(value);
+            }
+            _ => series.push(Timeseries {
+                variable_id: row.variable_id,
+                variable: row.variable,
+                unit: row.unit,
+                region_id: row.region_id,
+                region: row.region,
+                sector_id: row.sector_id,
+                sector: row.sector,
+                values: vec![value],
+            }),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(series))
+}
+
+/// Exports the pathway data of several scenarios as IAMC CSV, for tools such
+/// as pyam. Sector-specific series are exported as a `Variable|Sector`
+/// sub-variable, since the IAMC format has no sector column; see
+/// `timeseries_iamc_csv` for how clashing labels are handled.
+#[get("/timeseries/export")]
+async fn export_timeseries(
+    db: web::Data<PgPool>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let params = TimeseriesExportParams::from_query(req.query_string())?;
+    if params.scenario_id.is_empty() {
+        return Err(ApiError::BadRequestError("At least one scenario_id is required".into()));
+    }
+
+    let values = sqlx::query_as!(
+        IamcValue,
+        r#"
+        SELECT
+            COALESCE(s.model, p.name, '') as "model!",
+            s.title as scenario,
+            r.name as region,
+            CASE WHEN se.name IS NULL THEN v.name ELSE v.name || '|' || se.name END as "variable!",
+            u.name as unit,
+            t.year, t.value,
+            s.id as scenario_id
+        FROM pbtar.timeseries t
+        JOIN pbtar.scenarios s ON s.id = t.scenario_id
+        LEFT JOIN pbtar.publishers p ON p.id = s.publisher_id
+        JOIN pbtar.variables v ON v.id = t.variable_id
+        JOIN pbtar.units u ON u.id = v.unit_id
+        JOIN pbtar.regions r ON r.id = t.region_id
+        LEFT JOIN pbtar.sectors se ON se.id = t.sector_id
+        WHERE t.scenario_id = ANY($1)
+            AND (cardinality($2::int[]) = 0 OR t.variable_id = ANY($2))
+            AND (cardinality($3::text[]) = 0 OR v.name LIKE ANY($3))
+            AND (cardinality($4::int[]) = 0 OR t.region_id = ANY($4))
+            AND (cardinality($5::int[]) = 0 OR t.sector_id = ANY($5))
+            AND ($6::int IS NULL OR t.year >= $6)
+            AND ($7::int IS NULL OR t.year <= $7)
+        ORDER BY 1, 2, s.id, 3, 4, u.name, t.year
+        "#,
+        &params.scenario_id,
+        &params.variable_id,
+        &params.variable_patterns(),
+        &params.region_id,
+        &params.sector_id,
+        params.year_from,
+        params.year_to
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    let body = crate::export::timeseries_iamc_csv(&values, params.layout)?;
+    let filename = format!("timeseries-{}.csv", chrono::Utc::now().format("%Y-%m-%d"));
+
+    Ok(HttpResponse::Ok()
+        .content_type(ExportFormat::Csv.content_type())
+        .insert_header(header::ContentDisposition::attachment(filename))
+        .body(body))
+}
+
+/// Replaces a scenario and all of its associations.
+#[put("/{id}")]
+async fn replace_scenario(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<CreateScenarioRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+    scenarios::replace_scenario(&mut tx, id, &payload).await?;
+    scenarios::record_revision(&mut tx, id, RevisionAction::Update, Some(&user.0)).await?;
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    let response = load_scenario_detail(db.get_ref(), id).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Updates only the supplied fields of a scenario.
+#[patch("/{id}")]
+async fn update_scenario(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<UpdateScenarioRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+
+    let id = path.into_inner();
+
+    if let Some(title) = &payload.title {
+        scenarios::validate_required("title", title)?;
+    }
+    if let Some(type_name) = &payload.type_name {
+        scenarios::validate_required("type_name", type_name)?;
+    }
+    scenarios::validate_lengths(
+        payload.title.as_deref(),
+        payload.type_name.as_deref(),
+        payload.model.as_ref().and_then(Option::as_deref),
+        payload.temperature_target.as_ref().and_then(Option::as_deref),
+    )?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    scenarios::validate_publisher(&mut tx, payload.publisher_id.flatten()).await?;
+    if let Some(ids) = &payload.region_ids {
+        scenarios::REGIONS.validate(&mut tx, ids).await?;
+    }
+    if let Some(ids) = &payload.stakeholder_ids {
+        scenarios::STAKEHOLDERS.validate(&mut tx, ids).await?;
+    }
+    if let Some(ids) = &payload.sector_ids {
+        scenarios::SECTORS.validate(&mut tx, ids).await?;
+    }
+
+    // The parsed temperature columns are only replaced along with the label,
+    // and cleared with it.
+    let temperature_target = payload.temperature_target.as_ref().and_then(Option::as_deref).map(str::trim);
+    let temperature = temperature_target
+        .map(TemperatureTarget::parse)
+        .unwrap_or_default();
+
+    // Each nullable column comes with a flag telling whether it was supplied,
+    // so that `null` clears it while an omitted key keeps it.
+    sqlx::query_scalar!(
+        r#"
+        UPDATE pbtar.scenarios
+        SET title = COALESCE($2, title),
+            type = COALESCE($3, type),
+            temperature_target = CASE WHEN $4 THEN $5 ELSE temperature_target END,
+            temperature_lower = CASE WHEN $4 THEN $6 ELSE temperature_lower END,
+            temperature_upper = CASE WHEN $4 THEN $7 ELSE temperature_upper END,
+            temperature_probability = CASE WHEN $4 THEN $8 ELSE temperature_probability END,
+            temperature_overshoot = CASE WHEN $4 THEN $9 ELSE temperature_overshoot END,
+            description = CASE WHEN $10 THEN $11 ELSE description END,
+            publisher_id = CASE WHEN $12 THEN $13 ELSE publisher_id END,
+            published_date = CASE WHEN $14 THEN $15 ELSE published_date END,
+            target_year = CASE WHEN $16 THEN $17 ELSE target_year END,
+            model = CASE WHEN $18 THEN $19 ELSE model END
+        WHERE id = $1
+        RETURNING id
+        "#,
+        id,
+        payload.title.as_deref().map(str::trim),
+        payload.type_name.as_deref().map(str::trim),
+        payload.temperature_target.is_some(),
+        temperature_target,
+        temperature.lower,
+        temperature.upper,
+        temperature.probability,
+        temperature.overshoot,
+        payload.description.is_some(),
+        payload.description.as_ref().and_then(Option::as_deref).map(str::trim),
+        payload.publisher_id.is_some(),
+        payload.publisher_id.flatten(),
+        payload.published_date.is_some(),
+        payload.published_date.flatten(),
+        payload.target_year.is_some(),
+        payload.target_year.flatten(),
+        payload.model.is_some(),
+        payload.model.as_ref().and_then(Option::as_deref).map(str::trim)
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Scenario with id {} not found", id)))?;
+
+    if let Some(ids) = &payload.region_ids {
+        scenarios::REGIONS.replace(&mut tx, id, ids).await?;
+    }
+    if let Some(ids) = &payload.stakeholder_ids {
+        scenarios::STAKEHOLDERS.replace(&mut tx, id, ids).await?;
+    }
+    if let Some(ids) = &payload.sector_ids {
+        scenarios::SECTORS.replace(&mut tx, id, ids).await?;
+    }
+    if let Some(relations) = &payload.relations {
+        scenarios::replace_relations(&mut tx, id, relations).await?;
+    }
+
+    scenarios::record_revision(&mut tx, id, RevisionAction::Update, Some(&user.0)).await?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    let response = load_scenario_detail(db.get_ref(), id).await?;
+
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[delete("/{id}")]
+async fn delete_scenario(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    sqlx::query_scalar!("SELECT id FROM pbtar.scenarios WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::NotFoundError(format!("Scenario with id {} not found", id)))?;
+
+    // The final state is recorded first, so the history outlives the scenario.
+    scenarios::record_revision(&mut tx, id, RevisionAction::Delete, Some(&user.0)).await?;
+
+    // Junction rows are removed by ON DELETE CASCADE.
+    sqlx::query!("DELETE FROM pbtar.scenarios WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Counts the scenarios matching `filters` per value of `key`, an expression
+/// over the scenario `s`, leaving out NULL keys.
+async fn count_by<K>(db: &PgPool, filters: &ScenarioFilters, key: &str) -> Result<HashMap<K, i64>, ApiError>
+where
+    K: for<'r> sqlx::Decode<'r, Postgres> + sqlx::Type<Postgres> + Eq + Hash + Send + Unpin,
+{
+    let mut builder = QueryBuilder::new(format!("SELECT {} AS key, COUNT(*)", key));
+    push_from(&mut builder, filters);
+    builder.push(format!(" AND {} IS NOT NULL GROUP BY 1", key));
+
+    let counts: Vec<(K, i64)> = builder
+        .build_query_as()
+        .fetch_all(db)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(counts.into_iter().collect())
+}
+
+/// Counts the scenarios matching `filters` per entry of `association`.
+async fn count_by_association(
+    db: &PgPool,
+    filters: &ScenarioFilters,
+    association: &scenarios::Association,
+) -> Result<HashMap<i32, i64>, ApiError> {
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT j.{}, COUNT(*) FROM pbtar.{} j WHERE j.scenario_id IN (SELECT s.id",
+        association.column, association.junction
+    ));
+    push_from(&mut builder, filters);
+    builder.push(") GROUP BY 1");
+
+    let counts: Vec<(i32, i64)> = builder
+        .build_query_as()
+        .fetch_all(db)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(counts.into_iter().collect())
+}
+
+/// Counts the scenarios matching `filters` per region. With subregions
+/// and/or containing regions included, a region also counts the scenarios
+/// tagged with those, as selecting it would list them.
+async fn count_by_region(db: &PgPool, filters: &ScenarioFilters) -> Result<HashMap<i32, i64>, ApiError> {
+    if !filters.include_subregions && !filters.include_parent_regions {
+        return count_by_association(db, filters, &scenarios::REGIONS).await;
+    }
+
+    let mut builder = QueryBuilder::new("");
+    push_expanded_regions(&mut builder, filters, None);
+    builder.push(
+        "
+        SELECT e.root, COUNT(DISTINCT j.scenario_id)
+        FROM pbtar.scenario_regions j
+        JOIN expanded e ON j.region_id = e.id
+        WHERE j.scenario_id IN (SELECT s.id",
+    );
+    push_from(&mut builder, filters);
+    builder.push(") GROUP BY 1");
+
+    let counts: Vec<(i32, i64)> = builder
+        .build_query_as()
+        .fetch_all(db)
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(counts.into_iter().collect())
+}
+
+/// The filters each option of a dimension is counted under: the current
+/// ones, with the dimension's own selection dropped where selecting an
+/// option widens the list rather than narrowing it.
+struct FacetFilters {
+    publishers: ScenarioFilters,
+    regions: ScenarioFilters,
+    stakeholders: ScenarioFilters,
+    sectors: ScenarioFilters,
+    types: ScenarioFilters,
+    temperature_targets: ScenarioFilters,
+}
+
+impl FacetFilters {
+    fn new(filters: &ScenarioFilters) -> Self {
+        let with = |drop: fn(&mut ScenarioFilters)| {
+            let mut filters = filters.clone();
+            drop(&mut filters);
+            filters
+        };
+
+        Self {
+            publishers: with(|f| f.publisher_id.clear()),
+            regions: with(|f| {
+                if f.region_match == MatchMode::Any {
+                    f.region_id.clear();
+                }
+            }),
+            stakeholders: with(|f| {
+                if f.stakeholder_match == MatchMode::Any {
+                    f.stakeholder_id.clear();
+                }
+            }),
+            sectors: with(|f| {
+                if f.sector_match == MatchMode::Any {
+                    f.sector_id.clear();
+                }
+            }),
+            types: with(|f| f.type_name = None),
+            temperature_targets: with(|f| f.temperature_target = None),
+        }
+    }
+}
+
+/// Lists the options of every filter. With `facets=true`, each option
+/// carries the number of scenarios matching the other supplied
+/// `ScenarioFilters` together with that option, so that dead ends can be
+/// hidden. Under `include_subregions` or `include_parent_regions`, regions
+/// also count the scenarios tagged with their relatives.
 #[get("/filters/options")]
 async fn get_filter_options(
     db: web::Data<PgPool>,
+    req: HttpRequest,
+    params: web::Query<FilterOptionsParams>,
 ) -> Result<impl Responder, ApiError> {
     let publishers = sqlx::query!("SELECT id, name FROM pbtar.publishers ORDER BY name")
         .fetch_all(db.get_ref())
@@ -252,6 +1397,7 @@ async fn get_filter_options(
                 id: p.id,
                 name: p.name,
                 description: None,
+                website: None,
             })
             .collect(),
         regions: regions
@@ -279,7 +1425,7 @@ async fn get_filter_options(
             .collect(),
         types: types
             .into_iter()
-            .filter_map(|t| Some(t.r#type))
+            .map(|t| t.r#type)
             .collect(),
         temperature_targets: temperature_targets
             .into_iter()
@@ -287,14 +1433,107 @@ async fn get_filter_options(
             .collect(),
     };
 
-    Ok(HttpResponse::Ok().json(options))
+    if !params.facets {
+        return Ok(HttpResponse::Ok().json(options));
+    }
+
+    let filters = FacetFilters::new(&ScenarioFilters::from_query(req.query_string())?);
+    let db = db.get_ref();
+
+    let publisher_counts: HashMap<i32, i64> = count_by(db, &filters.publishers, "s.publisher_id").await?;
+    let region_counts = count_by_region(db, &filters.regions).await?;
+    let stakeholder_counts = count_by_association(db, &filters.stakeholders, &scenarios::STAKEHOLDERS).await?;
+    let sector_counts = count_by_association(db, &filters.sectors, &scenarios::SECTORS).await?;
+    let type_counts: HashMap<String, i64> = count_by(db, &filters.types, "s.type").await?;
+    let temperature_counts: HashMap<String, i64> =
+        count_by(db, &filters.temperature_targets, "s.temperature_target").await?;
+
+    fn with_counts<T>(options: Vec<T>, counts: &HashMap<i32, i64>, id: fn(&T) -> i32) -> Vec<Facet<T>> {
+        options
+            .into_iter()
+            .map(|option| Facet {
+                count: counts.get(&id(&option)).copied().unwrap_or(0),
+                option,
+            })
+            .collect()
+    }
+
+    fn values_with_counts(values: Vec<String>, counts: &HashMap<String, i64>) -> Vec<FacetValue> {
+        values
+            .into_iter()
+            .map(|value| FacetValue {
+                count: counts.get(&value).copied().unwrap_or(0),
+                value,
+            })
+            .collect()
+    }
+
+    let facets = FilterFacets {
+        publishers: with_counts(options.publishers, &publisher_counts, |p| p.id),
+        regions: with_counts(options.regions, &region_counts, |r| r.id),
+        stakeholders: with_counts(options.stakeholders, &stakeholder_counts, |s| s.id),
+        sectors: with_counts(options.sectors, &sector_counts, |s| s.id),
+        types: values_with_counts(options.types, &type_counts),
+        temperature_targets: values_with_counts(options.temperature_targets, &temperature_counts),
+    };
+
+    Ok(HttpResponse::Ok().json(facets))
+}
+
+/// Bulk imports scenarios from a CSV body. With `dry_run=true` every row is
+/// validated and reported but nothing is written.
+#[post("/import")]
+async fn import_scenarios(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    params: web::Query<ImportParams>,
+    body: web::Bytes,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+
+    let report = crate::import::import_scenarios_csv(db.get_ref(), &body, params.dry_run, Some(&user.0)).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Loads IAMC wide-format pathway data from a CSV or XLSX body into the
+/// time-series store of existing scenarios.
+#[post("/import/iamc")]
+async fn import_iamc(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    params: web::Query<IamcImportParams>,
+    body: web::Bytes,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Curator)?;
+    // Regions are part of the taxonomy, which only admins may extend.
+    if params.create_regions {
+        user.require(Role::Admin)?;
+    }
+
+    let report = crate::import::import_iamc(db.get_ref(), &body, params.dry_run, params.create_regions).await?;
+
+    Ok(HttpResponse::Ok().json(report))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/scenarios")
+            // Allow CSV imports well beyond the default 256 KiB payload limit.
+            .app_data(web::PayloadConfig::new(10 * 1024 * 1024))
             .service(list_scenarios)
+            // Registered ahead of `/{id}`, which would otherwise claim it.
+            .service(compare_scenarios)
             .service(get_scenario)
+            .service(get_scenario_timeseries)
+            .service(get_scenario_history)
+            .service(export_timeseries)
             .service(get_filter_options)
+            .service(create_scenario)
+            .service(replace_scenario)
+            .service(update_scenario)
+            .service(delete_scenario)
+            .service(import_scenarios)
+            .service(import_iamc)
     );
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedUser;
+use crate::db::taxonomy::SECTORS;
+use crate::errors::ApiError;
+use crate::models::{DeleteParams, Role, Sector, SectorRequest};
+
+#[get("")]
+async fn list_sectors(
+    db: web::Data<PgPool>,
+) -> Result<impl Responder, ApiError> {
+    let sectors = sqlx::query_as!(Sector, "SELECT id, name FROM pbtar.sectors ORDER BY name")
+        .fetch_all(db.get_ref())
+        .await
+        .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(sectors))
+}
+
+#[get("/{id}")]
+async fn get_sector(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+
+    let sector = sqlx::query_as!(Sector, "SELECT id, name FROM pbtar.sectors WHERE id = $1", id)
+        .fetch_optional(db.get_ref())
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::NotFoundError(format!("Sector with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(sector))
+}
+
+#[post("")]
+async fn create_sector(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    payload: web::Json<SectorRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    SECTORS.validate_name(&mut tx, &payload.name, None).await?;
+
+    let sector = sqlx::query_as!(
+        Sector,
+        "INSERT INTO pbtar.sectors (name) VALUES ($1) RETURNING id, name",
+        payload.name.trim()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| SECTORS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Created().json(sector))
+}
+
+#[put("/{id}")]
+async fn update_sector(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<SectorRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    SECTORS.lock(&mut tx, id).await?;
+    SECTORS.validate_name(&mut tx, &payload.name, Some(id)).await?;
+
+    let sector = sqlx::query_as!(
+        Sector,
+        "UPDATE pbtar.sectors SET name = $2 WHERE id = $1 RETURNING id, name",
+        id,
+        payload.name.trim()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| SECTORS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(sector))
+}
+
+/// Deletes a sector. Scenarios and pathway data referring to it are moved to
+/// `reassign_to`; without one, a sector in use cannot be deleted.
+#[delete("/{id}")]
+async fn delete_sector(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    params: web::Query<DeleteParams>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    SECTORS.delete(&mut tx, path.into_inner(), params.reassign_to, &user.0).await?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sectors")
+            .service(list_sectors)
+            .service(get_sector)
+            .service(create_sector)
+            .service(update_sector)
+            .service(delete_sector)
+    );
+}
@@ -0,0 +1,145 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedUser;
+use crate::db::scenarios::{validate_length, validate_required};
+use crate::db::taxonomy::{STAKEHOLDERS, STAKEHOLDER_TYPE_MAX_LENGTH};
+use crate::errors::ApiError;
+use crate::models::{DeleteParams, Role, Stakeholder, StakeholderRequest};
+
+#[get("")]
+async fn list_stakeholders(
+    db: web::Data<PgPool>,
+) -> Result<impl Responder, ApiError> {
+    let stakeholders = sqlx::query_as!(
+        Stakeholder,
+        "SELECT id, name, type as type_name FROM pbtar.stakeholders ORDER BY name"
+    )
+    .fetch_all(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(stakeholders))
+}
+
+#[get("/{id}")]
+async fn get_stakeholder(
+    db: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    let id = path.into_inner();
+
+    let stakeholder = sqlx::query_as!(
+        Stakeholder,
+        "SELECT id, name, type as type_name FROM pbtar.stakeholders WHERE id = $1",
+        id
+    )
+    .fetch_optional(db.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("Stakeholder with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(stakeholder))
+}
+
+#[post("")]
+async fn create_stakeholder(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    payload: web::Json<StakeholderRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    STAKEHOLDERS.validate_name(&mut tx, &payload.name, None).await?;
+    validate_required("type_name", &payload.type_name)?;
+    validate_length("type_name", &payload.type_name, STAKEHOLDER_TYPE_MAX_LENGTH)?;
+
+    let stakeholder = sqlx::query_as!(
+        Stakeholder,
+        r#"
+        INSERT INTO pbtar.stakeholders (name, type)
+        VALUES ($1, $2)
+        RETURNING id, name, type as type_name
+        "#,
+        payload.name.trim(),
+        payload.type_name.trim()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| STAKEHOLDERS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Created().json(stakeholder))
+}
+
+#[put("/{id}")]
+async fn update_stakeholder(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<StakeholderRequest>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    STAKEHOLDERS.lock(&mut tx, id).await?;
+    STAKEHOLDERS.validate_name(&mut tx, &payload.name, Some(id)).await?;
+    validate_required("type_name", &payload.type_name)?;
+    validate_length("type_name", &payload.type_name, STAKEHOLDER_TYPE_MAX_LENGTH)?;
+
+    let stakeholder = sqlx::query_as!(
+        Stakeholder,
+        r#"
+        UPDATE pbtar.stakeholders SET name = $2, type = $3
+        WHERE id = $1
+        RETURNING id, name, type as type_name
+        "#,
+        id,
+        payload.name.trim(),
+        payload.type_name.trim()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| STAKEHOLDERS.write_error(&payload.name, e))?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(stakeholder))
+}
+
+/// Deletes a stakeholder. Scenarios tagged with it are tagged with
+/// `reassign_to` instead; without one, a stakeholder in use cannot be deleted.
+#[delete("/{id}")]
+async fn delete_stakeholder(
+    db: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+    params: web::Query<DeleteParams>,
+) -> Result<impl Responder, ApiError> {
+    user.require(Role::Admin)?;
+
+    let mut tx = db.begin().await.map_err(ApiError::DbError)?;
+
+    STAKEHOLDERS.delete(&mut tx, path.into_inner(), params.reassign_to, &user.0).await?;
+
+    tx.commit().await.map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/stakeholders")
+            .service(list_stakeholders)
+            .service(get_stakeholder)
+            .service(create_stakeholder)
+            .service(update_stakeholder)
+            .service(delete_stakeholder)
+    );
+}
@@ -0,0 +1,91 @@
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedUser;
+use crate::errors::ApiError;
+use crate::models::{Role, UpdateUserRoleRequest, UserResponse};
+
+#[get("")]
+async fn list_users(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedUser,
+) -> Result<impl Responder, ApiError> {
+    auth.require(Role::Admin)?;
+
+    let users = sqlx::query_as!(
+        UserResponse,
+        r#"SELECT id, username, email, role as "role: Role" FROM pbtar.users ORDER BY username"#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(ApiError::DbError)?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// Changes the role of a user. Tokens already issued to the user carry the
+/// new role from their next request on.
+#[put("/{id}/role")]
+async fn update_user_role(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    payload: web::Json<UpdateUserRoleRequest>,
+) -> Result<impl Responder, ApiError> {
+    auth.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    if id == auth.0.sub && payload.role != Role::Admin {
+        return Err(ApiError::BadRequestError("Admins cannot demote themselves".into()));
+    }
+
+    let user = sqlx::query_as!(
+        UserResponse,
+        r#"
+        UPDATE pbtar.users SET role = $2
+        WHERE id = $1
+        RETURNING id, username, email, role as "role: Role"
+        "#,
+        id,
+        payload.role as Role
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(ApiError::DbError)?
+    .ok_or_else(|| ApiError::NotFoundError(format!("User with id {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+#[delete("/{id}")]
+async fn delete_user(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<impl Responder, ApiError> {
+    auth.require(Role::Admin)?;
+
+    let id = path.into_inner();
+
+    if id == auth.0.sub {
+        return Err(ApiError::BadRequestError("Admins cannot delete themselves".into()));
+    }
+
+    sqlx::query_scalar!("DELETE FROM pbtar.users WHERE id = $1 RETURNING id", id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(ApiError::DbError)?
+        .ok_or_else(|| ApiError::NotFoundError(format!("User with id {} not found", id)))?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/users")
+            .service(list_users)
+            .service(update_user_role)
+            .service(delete_user)
+    );
+}
\ No newline at end of file
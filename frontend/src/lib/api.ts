@@ -1,5 +1,16 @@
 import axios from 'axios';
-import type { ScenarioDetail, ScenarioFilters, ScenarioListItem, FilterOptions } from './types';
+import type {
+  ScenarioDetail,
+  ScenarioFilters,
+  ScenarioListItem,
+  FilterOptions,
+  FilterFacets,
+  Paginated,
+  ScenarioComparison,
+  ScenarioRevision,
+  Timeseries,
+  TimeseriesFilters
+} from './types';
 
 // Use our own server-side proxy to access the API
 // This ensures browser requests go through our proxy, not directly to the API container
@@ -13,23 +24,51 @@ const api = axios.create({
 });
 
 export const scenariosApi = {
-  // Get a list of scenarios with optional filters
-  getScenarios: async (filters?: ScenarioFilters): Promise<ScenarioListItem[]> => {
+  // Get a page of scenarios with optional filters
+  getScenarios: async (filters?: ScenarioFilters): Promise<Paginated<ScenarioListItem>> => {
     const params = filters || {};
     const response = await api.get('/scenarios', { params });
     return response.data;
   },
 
-  // Get detailed information about a specific scenario
-  getScenarioById: async (id: number): Promise<ScenarioDetail> => {
-    const response = await api.get(`/scenarios/${id}`);
+  // Compare several scenarios side by side
+  compareScenarios: async (ids: number[]): Promise<ScenarioComparison> => {
+    const response = await api.get('/scenarios/compare', { params: { ids: ids.join(',') } });
     return response.data;
   },
 
+  // Get detailed information about a specific scenario, optionally as of a past revision
+  getScenarioById: async (id: number, revision?: number): Promise<ScenarioDetail> => {
+    const response = await api.get(`/scenarios/${id}`, { params: { revision } });
+    return response.data;
+  },
+
+  // Get the revision history of a scenario, newest first
+  getScenarioHistory: async (id: number): Promise<ScenarioRevision[]> => {
+    const response = await api.get(`/scenarios/${id}/history`);
+    return response.data;
+  },
+
+  // Get the pathway data of a scenario
+  getTimeseries: async (id: number, filters?: TimeseriesFilters): Promise<Timeseries[]> => {
+    const response = await api.get(`/scenarios/${id}/timeseries`, { params: filters || {} });
+    return response.data;
+  },
+
+  // Link to the IAMC CSV export of several scenarios' pathway data
+  timeseriesExportUrl: (scenarioIds: number[], layout: 'wide' | 'long' = 'wide'): string =>
+    `${API_URL}/scenarios/timeseries/export?scenario_id=${scenarioIds.join(',')}&layout=${layout}`,
+
   // Get all available filter options
   getFilterOptions: async (): Promise<FilterOptions> => {
     const response = await api.get('/scenarios/filters/options');
     return response.data;
+  },
+
+  // Get all filter options, each with the number of scenarios matching it and the current filters
+  getFilterFacets: async (filters?: ScenarioFilters): Promise<FilterFacets> => {
+    const response = await api.get('/scenarios/filters/options', { params: { ...filters, facets: true } });
+    return response.data;
   }
 };
 
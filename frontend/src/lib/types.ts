@@ -2,6 +2,7 @@ export interface Publisher {
   id: number;
   name: string;
   description?: string;
+  website?: string;
 }
 
 export interface Region {
@@ -10,6 +11,12 @@ export interface Region {
   parent_id?: number;
 }
 
+export interface RegionNode {
+  id: number;
+  name: string;
+  children: RegionNode[];
+}
+
 export interface Stakeholder {
   id: number;
   name: string;
@@ -26,17 +33,29 @@ export interface ScenarioListItem {
   title: string;
   type_name: string;
   temperature_target?: string;
+  temperature_lower?: number;
+  temperature_upper?: number;
+  temperature_probability?: number;
+  temperature_overshoot?: boolean;
   description?: string;
   publisher?: string;
   published_date?: string;
   target_year?: number;
+  rank?: number;
+  title_highlight?: string;
+  description_highlight?: string;
 }
 
 export interface ScenarioDetail {
   id: number;
   title: string;
   type_name: string;
+  model?: string;
   temperature_target?: string;
+  temperature_lower?: number;
+  temperature_upper?: number;
+  temperature_probability?: number;
+  temperature_overshoot?: boolean;
   description?: string;
   published_date?: string;
   target_year?: number;
@@ -44,17 +63,90 @@ export interface ScenarioDetail {
   regions: Region[];
   stakeholders: Stakeholder[];
   sectors: Sector[];
+  relations: ScenarioRelation[];
+  variables: Variable[];
+}
+
+export type RelationKind = 'supersedes' | 'derived_from' | 'part_of_series';
+
+export interface ScenarioRelation {
+  relation: RelationKind;
+  direction: 'outgoing' | 'incoming';
+  scenario_id: number;
+  // null when a past revision refers to a scenario deleted since
+  title: string | null;
+}
+
+export interface Variable {
+  id: number;
+  name: string;
+  unit: string;
+  description?: string;
+}
+
+export interface TimeseriesValue {
+  year: number;
+  value: number;
+}
+
+export interface Timeseries {
+  variable_id: number;
+  variable: string;
+  unit: string;
+  region_id: number;
+  region: string;
+  sector_id?: number;
+  sector?: string;
+  values: TimeseriesValue[];
 }
 
+export interface TimeseriesFilters {
+  variable_id?: number[] | string;
+  variable?: string;
+  region_id?: number[] | string;
+  sector_id?: number[] | string;
+  year_from?: number;
+  year_to?: number;
+}
+
+export type MatchMode = 'any' | 'all';
+
 export interface ScenarioFilters {
   publisher_id?: number;
   region_id?: number;
+  region_match?: MatchMode;
+  include_subregions?: boolean;
+  include_parent_regions?: boolean;
   stakeholder_id?: number;
+  stakeholder_match?: MatchMode;
   sector_id?: number;
+  sector_match?: MatchMode;
+  exclude_publisher_id?: number;
+  exclude_region_id?: number;
+  exclude_stakeholder_id?: number;
+  exclude_sector_id?: number;
+  q?: string;
   type_name?: string;
   temperature_target?: string;
+  temp_min?: number;
+  temp_max?: number;
   year_from?: number;
   year_to?: number;
+  sort?: string;
+  latest_only?: boolean;
+  page?: number;
+  per_page?: number;
+  format?: 'json' | 'csv' | 'xlsx';
+}
+
+export interface Paginated<T> {
+  items: T[];
+  total: number;
+  page: number;
+  per_page: number;
+  total_pages: number;
+  next?: string;
+  prev?: string;
 }
 
 export interface FilterOptions {
@@ -64,4 +156,67 @@ export interface FilterOptions {
   sectors: Sector[];
   types: string[];
   temperature_targets: string[];
-}
\ No newline at end of file
+}
+
+// A filter option with the number of scenarios selecting it would list
+export type Facet<T> = T & { count: number };
+
+export interface FacetValue {
+  value: string;
+  count: number;
+}
+
+export interface FilterFacets {
+  publishers: Facet<Publisher>[];
+  regions: Facet<Region>[];
+  stakeholders: Facet<Stakeholder>[];
+  sectors: Facet<Sector>[];
+  types: FacetValue[];
+  temperature_targets: FacetValue[];
+}
+
+export interface FieldComparison {
+  field: string;
+  values: unknown[];
+  same: boolean;
+}
+
+export interface SetComparison<T> {
+  common: T[];
+  unique: T[][];
+}
+
+export interface AlignedTimeseries {
+  variable_id: number;
+  variable: string;
+  unit: string;
+  region_id: number;
+  region: string;
+  sector_id?: number;
+  sector?: string;
+  values: { year: number; values: (number | null)[] }[];
+}
+
+export interface ScenarioComparison {
+  scenarios: ScenarioDetail[];
+  fields: FieldComparison[];
+  regions: SetComparison<Region>;
+  sectors: SetComparison<Sector>;
+  stakeholders: SetComparison<Stakeholder>;
+  timeseries: AlignedTimeseries[];
+}
+
+export interface FieldChange {
+  field: string;
+  from: unknown;
+  to: unknown;
+}
+
+export interface ScenarioRevision {
+  revision: number;
+  action: 'create' | 'update' | 'delete';
+  user_id?: number;
+  username?: string;
+  created_at: string;
+  changes: FieldChange[];
+}